@@ -0,0 +1,62 @@
+-- Номер телефона клиента хранится в формате E.164 и должен быть уникальным.
+-- Сначала старые номера приводятся к E.164 по тем же правилам, что и
+-- normalize_phone: номера без кода страны считаются российскими
+WITH digits AS (
+    SELECT
+        customer_id,
+        phone,
+        btrim(phone) LIKE '+%' AS has_plus,
+        regexp_replace(phone, '[^0-9]', '', 'g') AS d
+    FROM customer
+    WHERE phone ~ '^\s*\+?[0-9 ()-]+\s*$'
+),
+normalized AS (
+    SELECT
+        customer_id,
+        phone,
+        '+' || CASE
+            WHEN NOT has_plus AND length(d) = 11 AND d LIKE '8%' THEN '7' || substr(d, 2)
+            WHEN NOT has_plus AND length(d) = 10 THEN '7' || d
+            ELSE d
+        END AS normalized_phone
+    FROM digits
+    WHERE length(d) BETWEEN 8 AND 15
+),
+-- Из клиентов, чьи номера совпали после приведения, номер в E.164 получает
+-- один: тот, у кого он уже записан так, иначе самый ранний. Остальные
+-- помечаются ниже до ручного объединения карточек
+ranked AS (
+    SELECT
+        customer_id,
+        phone,
+        normalized_phone,
+        row_number() OVER (
+            PARTITION BY normalized_phone
+            ORDER BY phone = normalized_phone DESC, customer_id
+        ) AS n
+    FROM normalized
+    WHERE normalized_phone NOT LIKE '+0%'
+)
+UPDATE customer c
+SET phone = r.normalized_phone
+FROM ranked r
+WHERE c.customer_id = r.customer_id
+AND r.n = 1
+AND r.phone <> r.normalized_phone;
+
+-- Номера, которые так и не стали E.164 (пустые, нераспознанные, совпавшие
+-- после приведения), и точные повторы уже приведённых номеров помечаются
+-- номером клиента, иначе уникальный индекс не создастся на старых данных.
+-- Исходное значение остаётся после двоеточия для ручного разбора
+UPDATE customer c
+SET phone = 'legacy-' || c.customer_id || ':' || btrim(c.phone)
+WHERE c.phone NOT LIKE 'legacy-%'
+AND (
+    c.phone !~ '^\+[1-9][0-9]{7,14}$'
+    OR EXISTS(
+        SELECT 1 FROM customer o
+        WHERE o.phone = c.phone AND o.customer_id < c.customer_id
+    )
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS customer_phone_key ON customer (phone);
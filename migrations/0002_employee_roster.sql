@@ -0,0 +1,12 @@
+-- Сотрудников не удаляют, а деактивируют: на них ссылаются продажи и заказы фильмов
+ALTER TABLE employee ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+
+-- Численность персонала считается по employee, а не вводится вручную
+ALTER TABLE cinema DROP COLUMN IF EXISTS employee_count;
+
+-- Число активных сотрудников кинотеатра, общее для всех запросов к кинотеатрам
+CREATE OR REPLACE FUNCTION cinema_employee_count(p_cinema_id INTEGER)
+RETURNS INTEGER
+LANGUAGE sql STABLE AS $$
+    SELECT COUNT(*)::int FROM employee WHERE cinema_id = p_cinema_id AND is_active
+$$;
@@ -0,0 +1,4 @@
+-- Статус заказа копии фильма у дистрибьютора
+ALTER TABLE film_order
+    ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'ordered'
+    CHECK (status IN ('ordered', 'delivered', 'returned', 'cancelled'));
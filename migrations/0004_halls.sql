@@ -0,0 +1,40 @@
+-- Залы кинотеатра вместо счётчика cinema.hall_count
+CREATE TABLE IF NOT EXISTS hall (
+    hall_id SERIAL PRIMARY KEY,
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    capacity INTEGER NOT NULL CHECK (capacity > 0),
+    screen_type VARCHAR(20) NOT NULL DEFAULT 'standard'
+        CHECK (screen_type IN ('standard', '3d', 'imax', '4dx')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    UNIQUE (cinema_id, name)
+);
+
+ALTER TABLE session ADD COLUMN IF NOT EXISTS hall_id INTEGER REFERENCES hall (hall_id);
+
+-- Для уже заведённых кинотеатров hall_count превращается в столько же залов.
+-- Вместимость и экран старой схемой не хранились, поэтому залы создаются
+-- неактивными с вместимостью 1: их нужно заполнить через PUT и включить
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'cinema' AND column_name = 'hall_count'
+    ) THEN
+        INSERT INTO hall (cinema_id, name, capacity, is_active)
+        SELECT c.cinema_id, 'Зал ' || n, 1, FALSE
+        FROM cinema c
+        CROSS JOIN LATERAL generate_series(1, c.hall_count) AS n
+        ON CONFLICT (cinema_id, name) DO NOTHING;
+    END IF;
+END
+$$;
+
+ALTER TABLE cinema DROP COLUMN IF EXISTS hall_count;
+
+-- Число активных залов кинотеатра, общее для всех запросов к кинотеатрам
+CREATE OR REPLACE FUNCTION cinema_hall_count(p_cinema_id INTEGER)
+RETURNS INTEGER
+LANGUAGE sql STABLE AS $$
+    SELECT COUNT(*)::int FROM hall WHERE cinema_id = p_cinema_id AND is_active
+$$;
@@ -0,0 +1,12 @@
+-- Схема зала: места по рядам; column_index задаёт позицию в сетке, пропуски — проходы
+CREATE TABLE IF NOT EXISTS seat (
+    seat_id SERIAL PRIMARY KEY,
+    hall_id INTEGER NOT NULL REFERENCES hall (hall_id) ON DELETE CASCADE,
+    row_number INTEGER NOT NULL CHECK (row_number > 0),
+    seat_number INTEGER NOT NULL CHECK (seat_number > 0),
+    column_index INTEGER NOT NULL CHECK (column_index >= 0),
+    category VARCHAR(20) NOT NULL DEFAULT 'standard'
+        CHECK (category IN ('standard', 'vip', 'sofa', 'wheelchair')),
+    UNIQUE (hall_id, row_number, seat_number),
+    UNIQUE (hall_id, row_number, column_index)
+);
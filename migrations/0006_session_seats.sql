@@ -0,0 +1,23 @@
+-- Места, занятые на конкретном сеансе. Первичный ключ исключает двойную продажу места
+CREATE TABLE IF NOT EXISTS session_seat (
+    session_id INTEGER NOT NULL REFERENCES session (session_id) ON DELETE CASCADE,
+    seat_id INTEGER NOT NULL REFERENCES seat (seat_id),
+    booking_id INTEGER REFERENCES booking (booking_id) ON DELETE CASCADE,
+    sale_id INTEGER REFERENCES ticket_sale (sale_id) ON DELETE CASCADE,
+    PRIMARY KEY (session_id, seat_id),
+    CHECK ((booking_id IS NULL) <> (sale_id IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS session_seat_booking_idx ON session_seat (booking_id);
+CREATE INDEX IF NOT EXISTS session_seat_sale_idx ON session_seat (sale_id);
+
+-- Схему зала можно менять, пока места не заняты на предстоящих сеансах.
+-- Места, на которые ссылаются прошедшие сеансы, не удаляются, а становятся
+-- неактивными; уникальность номеров проверяется только среди активных мест
+ALTER TABLE seat ADD COLUMN IF NOT EXISTS is_active BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE seat DROP CONSTRAINT IF EXISTS seat_hall_id_row_number_seat_number_key;
+ALTER TABLE seat DROP CONSTRAINT IF EXISTS seat_hall_id_row_number_column_index_key;
+CREATE UNIQUE INDEX IF NOT EXISTS seat_number_key
+    ON seat (hall_id, row_number, seat_number) WHERE is_active;
+CREATE UNIQUE INDEX IF NOT EXISTS seat_position_key
+    ON seat (hall_id, row_number, column_index) WHERE is_active;
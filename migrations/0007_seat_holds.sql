@@ -0,0 +1,19 @@
+-- Временное удержание мест на время оплаты; истёкшие удержания удаляет фоновая задача
+CREATE TABLE IF NOT EXISTS seat_hold (
+    hold_id SERIAL PRIMARY KEY,
+    session_id INTEGER NOT NULL REFERENCES session (session_id) ON DELETE CASCADE,
+    customer_id INTEGER REFERENCES customer (customer_id),
+    ticket_count INTEGER NOT NULL CHECK (ticket_count > 0),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS seat_hold_expires_idx ON seat_hold (expires_at);
+CREATE INDEX IF NOT EXISTS seat_hold_session_idx ON seat_hold (session_id);
+
+ALTER TABLE session_seat
+    ADD COLUMN IF NOT EXISTS hold_id INTEGER REFERENCES seat_hold (hold_id) ON DELETE CASCADE;
+
+ALTER TABLE session_seat DROP CONSTRAINT IF EXISTS session_seat_check;
+ALTER TABLE session_seat
+    ADD CONSTRAINT session_seat_holder_check CHECK (num_nonnulls(booking_id, sale_id, hold_id) = 1);
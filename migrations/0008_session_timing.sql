@@ -0,0 +1,19 @@
+-- Хронометраж фильма и технические интервалы кинотеатра для проверки пересечений сеансов.
+-- Настоящего хронометража у уже заведённых фильмов нет. Им явно проставляется
+-- оценка в 120 минут с пометкой duration_estimated, чтобы у существующих сеансов
+-- было время окончания. Новые сеансы таких фильмов ставить нельзя,
+-- пока хронометраж не указан через PUT /films/{id}
+ALTER TABLE film ADD COLUMN IF NOT EXISTS duration_minutes INTEGER
+    CHECK (duration_minutes > 0);
+ALTER TABLE film ADD COLUMN IF NOT EXISTS duration_estimated BOOLEAN NOT NULL DEFAULT FALSE;
+UPDATE film
+SET duration_minutes = 120, duration_estimated = TRUE
+WHERE duration_minutes IS NULL;
+ALTER TABLE film ALTER COLUMN duration_minutes SET NOT NULL;
+
+ALTER TABLE cinema ADD COLUMN IF NOT EXISTS ad_minutes INTEGER NOT NULL DEFAULT 15
+    CHECK (ad_minutes >= 0);
+ALTER TABLE cinema ADD COLUMN IF NOT EXISTS cleaning_minutes INTEGER NOT NULL DEFAULT 15
+    CHECK (cleaning_minutes >= 0);
+
+CREATE INDEX IF NOT EXISTS session_hall_start_idx ON session (hall_id, start_time);
@@ -0,0 +1,22 @@
+-- Часы работы по дням недели (1 — понедельник, 7 — воскресенье).
+-- Дни без записи работают по cinema.opening_time/closing_time
+CREATE TABLE IF NOT EXISTS cinema_hours (
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    weekday SMALLINT NOT NULL CHECK (weekday BETWEEN 1 AND 7),
+    is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+    opening_time TIME NOT NULL,
+    closing_time TIME NOT NULL,
+    PRIMARY KEY (cinema_id, weekday)
+);
+
+-- Исключения на конкретные даты: праздничные выходные и продлённые часы работы
+CREATE TABLE IF NOT EXISTS cinema_hours_exception (
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    is_closed BOOLEAN NOT NULL DEFAULT FALSE,
+    opening_time TIME,
+    closing_time TIME,
+    note VARCHAR(255),
+    PRIMARY KEY (cinema_id, date),
+    CHECK (is_closed OR (opening_time IS NOT NULL AND closing_time IS NOT NULL))
+);
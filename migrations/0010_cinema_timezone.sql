@@ -0,0 +1,60 @@
+-- Часовой пояс кинотеатра (имя из базы IANA, например 'Asia/Yekaterinburg')
+ALTER TABLE cinema ADD COLUMN IF NOT EXISTS timezone TEXT NOT NULL DEFAULT 'Europe/Moscow';
+
+-- Начало сеанса хранилось как местное время кинотеатра.
+-- В USING нельзя использовать подзапрос, поэтому столбец пересоздаётся.
+-- Пересоздание выполняется, только пока start_time ещё без часового пояса
+DO $$
+BEGIN
+    IF EXISTS (
+        SELECT 1
+        FROM information_schema.columns
+        WHERE table_schema = current_schema()
+        AND table_name = 'session'
+        AND column_name = 'start_time'
+        AND data_type = 'timestamp without time zone'
+    ) THEN
+        ALTER TABLE session ADD COLUMN IF NOT EXISTS start_time_tz TIMESTAMPTZ;
+
+        UPDATE session s
+        SET start_time_tz = s.start_time AT TIME ZONE c.timezone
+        FROM cinema c
+        WHERE c.cinema_id = s.cinema_id;
+
+        DROP INDEX IF EXISTS session_hall_start_idx;
+        ALTER TABLE session DROP COLUMN start_time;
+        ALTER TABLE session RENAME COLUMN start_time_tz TO start_time;
+        ALTER TABLE session ALTER COLUMN start_time SET NOT NULL;
+    END IF;
+END $$;
+
+CREATE INDEX IF NOT EXISTS session_hall_start_idx ON session (hall_id, start_time);
+
+-- Остальные отметки времени записывались через NOW() во времени сервера
+ALTER TABLE booking ALTER COLUMN booking_time TYPE TIMESTAMPTZ;
+ALTER TABLE ticket_sale ALTER COLUMN sale_time TYPE TIMESTAMPTZ;
+ALTER TABLE seat_hold ALTER COLUMN created_at TYPE TIMESTAMPTZ;
+ALTER TABLE seat_hold ALTER COLUMN expires_at TYPE TIMESTAMPTZ;
+
+-- Момент времени в виде местного времени кинотеатра со смещением:
+-- '2025-03-01T19:30:00+05:00'
+CREATE OR REPLACE FUNCTION to_local_iso(ts TIMESTAMPTZ, tz TEXT) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT to_char(ts AT TIME ZONE tz, 'YYYY-MM-DD"T"HH24:MI:SS')
+        || CASE WHEN o.utc_offset < INTERVAL '0' THEN '-' ELSE '+' END
+        || to_char(abs_offset, 'HH24:MI')
+    FROM (SELECT (ts AT TIME ZONE tz) - (ts AT TIME ZONE 'UTC') AS utc_offset) o,
+        LATERAL (
+            SELECT CASE WHEN o.utc_offset < INTERVAL '0' THEN -o.utc_offset ELSE o.utc_offset END
+                AS abs_offset
+        ) a
+$$;
+
+-- Часовой пояс кинотеатра, в котором идёт сеанс
+CREATE OR REPLACE FUNCTION session_timezone(p_session_id INTEGER) RETURNS TEXT
+LANGUAGE sql STABLE AS $$
+    SELECT c.timezone
+    FROM session s
+    JOIN cinema c ON s.cinema_id = c.cinema_id
+    WHERE s.session_id = p_session_id
+$$;
@@ -0,0 +1,14 @@
+-- Отменённый сеанс остаётся в базе вместе с историей продаж
+ALTER TABLE session
+    ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'scheduled'
+    CHECK (status IN ('scheduled', 'cancelled'));
+ALTER TABLE session ADD COLUMN IF NOT EXISTS cancelled_at TIMESTAMPTZ;
+
+-- Возврат денег за продажу; на одну продажу — не больше одного возврата
+CREATE TABLE IF NOT EXISTS refund (
+    refund_id SERIAL PRIMARY KEY,
+    sale_id INTEGER NOT NULL UNIQUE REFERENCES ticket_sale (sale_id),
+    amount DOUBLE PRECISION NOT NULL CHECK (amount >= 0),
+    reason VARCHAR(50) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
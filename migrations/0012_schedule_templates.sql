@@ -0,0 +1,16 @@
+-- Шаблон расписания: фильм в зале в указанные дни недели и часы
+-- в пределах периода. Время — местное время кинотеатра
+CREATE TABLE IF NOT EXISTS schedule_template (
+    template_id SERIAL PRIMARY KEY,
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    film_id INTEGER NOT NULL REFERENCES film (film_id) ON DELETE CASCADE,
+    hall_id INTEGER REFERENCES hall (hall_id) ON DELETE CASCADE,
+    ticket_price DOUBLE PRECISION NOT NULL CHECK (ticket_price >= 0),
+    weekdays INTEGER[] NOT NULL CHECK (cardinality(weekdays) > 0 AND weekdays <@ '{1,2,3,4,5,6,7}'),
+    times TIME[] NOT NULL CHECK (cardinality(times) > 0),
+    start_date DATE NOT NULL,
+    end_date DATE NOT NULL,
+    CHECK (start_date <= end_date)
+);
+
+CREATE INDEX IF NOT EXISTS schedule_template_cinema_idx ON schedule_template (cinema_id);
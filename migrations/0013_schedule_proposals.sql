@@ -0,0 +1,26 @@
+-- Предложенная оптимизатором сетка сеансов на неделю.
+-- Сотрудники просматривают черновик, убирают лишнее и утверждают его
+CREATE TABLE IF NOT EXISTS schedule_proposal (
+    proposal_id SERIAL PRIMARY KEY,
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    week_start DATE NOT NULL,
+    ticket_price DOUBLE PRECISION NOT NULL CHECK (ticket_price >= 0),
+    status VARCHAR(20) NOT NULL DEFAULT 'draft' CHECK (status IN ('draft', 'committed')),
+    warnings TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- start_time — местное время кинотеатра, как в шаблонах расписания
+CREATE TABLE IF NOT EXISTS schedule_proposal_item (
+    item_id SERIAL PRIMARY KEY,
+    proposal_id INTEGER NOT NULL REFERENCES schedule_proposal (proposal_id) ON DELETE CASCADE,
+    film_id INTEGER NOT NULL REFERENCES film (film_id) ON DELETE CASCADE,
+    hall_id INTEGER NOT NULL REFERENCES hall (hall_id) ON DELETE CASCADE,
+    start_time TIMESTAMP NOT NULL,
+    expected_tickets INTEGER NOT NULL,
+    expected_revenue DOUBLE PRECISION NOT NULL,
+    session_id INTEGER REFERENCES session (session_id) ON DELETE SET NULL
+);
+
+CREATE INDEX IF NOT EXISTS schedule_proposal_item_proposal_idx
+    ON schedule_proposal_item (proposal_id);
@@ -0,0 +1,3 @@
+-- Списки сеансов фильтруются по кинотеатру и дате
+CREATE INDEX IF NOT EXISTS session_cinema_start_idx ON session (cinema_id, start_time);
+CREATE INDEX IF NOT EXISTS session_film_start_idx ON session (film_id, start_time);
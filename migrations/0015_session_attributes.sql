@@ -0,0 +1,35 @@
+-- Формат показа и звуковая дорожка сеанса
+ALTER TABLE session
+    ADD COLUMN IF NOT EXISTS format VARCHAR(10) NOT NULL DEFAULT '2d'
+    CHECK (format IN ('2d', '3d', 'imax', '4dx'));
+ALTER TABLE session ADD COLUMN IF NOT EXISTS audio_language VARCHAR(3) NOT NULL DEFAULT 'ru';
+ALTER TABLE session ADD COLUMN IF NOT EXISTS subtitle_language VARCHAR(3);
+ALTER TABLE session ADD COLUMN IF NOT EXISTS audio_description BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE schedule_template
+    ADD COLUMN IF NOT EXISTS format VARCHAR(10) NOT NULL DEFAULT '2d'
+    CHECK (format IN ('2d', '3d', 'imax', '4dx'));
+ALTER TABLE schedule_template ADD COLUMN IF NOT EXISTS audio_language VARCHAR(3) NOT NULL DEFAULT 'ru';
+ALTER TABLE schedule_template ADD COLUMN IF NOT EXISTS subtitle_language VARCHAR(3);
+ALTER TABLE schedule_template
+    ADD COLUMN IF NOT EXISTS audio_description BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE INDEX IF NOT EXISTS session_format_idx ON session (format);
+
+-- Доплата за формат поверх ticket_price сеанса, своя у каждого кинотеатра
+CREATE TABLE IF NOT EXISTS format_surcharge (
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    format VARCHAR(10) NOT NULL CHECK (format IN ('2d', '3d', 'imax', '4dx')),
+    amount DOUBLE PRECISION NOT NULL CHECK (amount >= 0),
+    PRIMARY KEY (cinema_id, format)
+);
+
+-- Доплата за формат в кинотеатре, 0 если не задана
+CREATE OR REPLACE FUNCTION session_surcharge(p_cinema_id INTEGER, p_format TEXT)
+RETURNS DOUBLE PRECISION
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(
+        (SELECT amount FROM format_surcharge WHERE cinema_id = p_cinema_id AND format = p_format),
+        0
+    )
+$$;
@@ -0,0 +1,31 @@
+-- Деньги хранятся точно: NUMERIC с копейками вместо DOUBLE PRECISION.
+-- Все суммы кинотеатра — в его валюте (код ISO 4217)
+ALTER TABLE cinema ADD COLUMN IF NOT EXISTS currency CHAR(3) NOT NULL DEFAULT 'RUB'
+    CHECK (currency ~ '^[A-Z]{3}$');
+
+ALTER TABLE session
+    ALTER COLUMN ticket_price TYPE NUMERIC(12, 2) USING ROUND(ticket_price::numeric, 2);
+ALTER TABLE film_order
+    ALTER COLUMN rental_cost TYPE NUMERIC(12, 2) USING ROUND(rental_cost::numeric, 2);
+ALTER TABLE refund
+    ALTER COLUMN amount TYPE NUMERIC(12, 2) USING ROUND(amount::numeric, 2);
+ALTER TABLE format_surcharge
+    ALTER COLUMN amount TYPE NUMERIC(12, 2) USING ROUND(amount::numeric, 2);
+ALTER TABLE schedule_template
+    ALTER COLUMN ticket_price TYPE NUMERIC(12, 2) USING ROUND(ticket_price::numeric, 2);
+ALTER TABLE schedule_proposal
+    ALTER COLUMN ticket_price TYPE NUMERIC(12, 2) USING ROUND(ticket_price::numeric, 2);
+ALTER TABLE schedule_proposal_item
+    ALTER COLUMN expected_revenue TYPE NUMERIC(12, 2) USING ROUND(expected_revenue::numeric, 2);
+
+-- Тип результата меняется, поэтому функцию нужно пересоздать
+DROP FUNCTION IF EXISTS session_surcharge(INTEGER, TEXT);
+
+CREATE FUNCTION session_surcharge(p_cinema_id INTEGER, p_format TEXT)
+RETURNS NUMERIC
+LANGUAGE sql STABLE AS $$
+    SELECT COALESCE(
+        (SELECT amount FROM format_surcharge WHERE cinema_id = p_cinema_id AND format = p_format),
+        0
+    )
+$$;
@@ -0,0 +1,24 @@
+-- Продажа хранит цену, по которой она прошла: изменение цены сеанса
+-- или доплаты за формат не переписывает уже полученную выручку
+ALTER TABLE ticket_sale ADD COLUMN IF NOT EXISTS unit_price NUMERIC(12, 2);
+ALTER TABLE ticket_sale ADD COLUMN IF NOT EXISTS discount NUMERIC(12, 2) NOT NULL DEFAULT 0
+    CHECK (discount >= 0);
+ALTER TABLE ticket_sale ADD COLUMN IF NOT EXISTS total_price NUMERIC(12, 2);
+ALTER TABLE ticket_sale ADD COLUMN IF NOT EXISTS currency CHAR(3);
+
+-- Для старых продаж другой цены, кроме текущей цены сеанса, нет
+UPDATE ticket_sale ts
+SET
+    unit_price = s.ticket_price + session_surcharge(s.cinema_id, s.format),
+    total_price = ts.ticket_count * (s.ticket_price + session_surcharge(s.cinema_id, s.format)),
+    currency = c.currency
+FROM session s
+JOIN cinema c ON s.cinema_id = c.cinema_id
+WHERE ts.session_id = s.session_id AND ts.unit_price IS NULL;
+
+ALTER TABLE ticket_sale ALTER COLUMN unit_price SET NOT NULL;
+ALTER TABLE ticket_sale ALTER COLUMN total_price SET NOT NULL;
+ALTER TABLE ticket_sale ALTER COLUMN currency SET NOT NULL;
+
+ALTER TABLE ticket_sale ADD CONSTRAINT ticket_sale_total_check
+    CHECK (total_price = ticket_count * unit_price - discount AND total_price >= 0);
@@ -0,0 +1,62 @@
+-- Типы билетов кинотеатра. Цена билета считается от базовой цены сеанса
+-- (ticket_price + доплата за формат): base * percent / 100 + adjustment, не меньше нуля
+CREATE TABLE IF NOT EXISTS ticket_type (
+    ticket_type_id SERIAL PRIMARY KEY,
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    code VARCHAR(20) NOT NULL CHECK (code ~ '^[a-z][a-z0-9_]*$'),
+    name VARCHAR(100) NOT NULL,
+    percent NUMERIC(5, 2) NOT NULL DEFAULT 100 CHECK (percent >= 0),
+    adjustment NUMERIC(12, 2) NOT NULL DEFAULT 0,
+    is_child BOOLEAN NOT NULL DEFAULT FALSE,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    UNIQUE (cinema_id, code)
+);
+
+-- Стандартный набор для уже существующих кинотеатров
+INSERT INTO ticket_type (cinema_id, code, name, percent, is_child)
+SELECT c.cinema_id, t.code, t.name, t.percent, t.is_child
+FROM cinema c
+CROSS JOIN (
+    VALUES
+        ('adult', 'Взрослый', 100, FALSE),
+        ('child', 'Детский', 70, TRUE),
+        ('student', 'Студенческий', 80, FALSE),
+        ('senior', 'Пенсионный', 70, FALSE)
+) AS t (code, name, percent, is_child)
+ON CONFLICT (cinema_id, code) DO NOTHING;
+
+-- Состав продажи по типам билетов с ценой на момент продажи.
+-- ticket_sale.unit_price теперь базовая цена сеанса, из которой считались строки
+CREATE TABLE IF NOT EXISTS ticket_sale_item (
+    sale_id INTEGER NOT NULL REFERENCES ticket_sale (sale_id) ON DELETE CASCADE,
+    ticket_type_id INTEGER NOT NULL REFERENCES ticket_type (ticket_type_id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    unit_price NUMERIC(12, 2) NOT NULL CHECK (unit_price >= 0),
+    PRIMARY KEY (sale_id, ticket_type_id)
+);
+
+-- Старые продажи целиком считаются взрослыми по сохранённой цене
+INSERT INTO ticket_sale_item (sale_id, ticket_type_id, quantity, unit_price)
+SELECT ts.sale_id, tt.ticket_type_id, ts.ticket_count, ts.unit_price
+FROM ticket_sale ts
+JOIN session s ON ts.session_id = s.session_id
+JOIN ticket_type tt ON tt.cinema_id = s.cinema_id AND tt.code = 'adult'
+ON CONFLICT DO NOTHING;
+
+ALTER TABLE ticket_sale DROP CONSTRAINT IF EXISTS ticket_sale_total_check;
+ALTER TABLE ticket_sale ADD CONSTRAINT ticket_sale_total_check CHECK (total_price >= 0);
+
+-- Состав брони; цена определяется при продаже
+CREATE TABLE IF NOT EXISTS booking_item (
+    booking_id INTEGER NOT NULL REFERENCES booking (booking_id) ON DELETE CASCADE,
+    ticket_type_id INTEGER NOT NULL REFERENCES ticket_type (ticket_type_id),
+    quantity INTEGER NOT NULL CHECK (quantity > 0),
+    PRIMARY KEY (booking_id, ticket_type_id)
+);
+
+INSERT INTO booking_item (booking_id, ticket_type_id, quantity)
+SELECT b.booking_id, tt.ticket_type_id, b.ticket_count
+FROM booking b
+JOIN session s ON b.session_id = s.session_id
+JOIN ticket_type tt ON tt.cinema_id = s.cinema_id AND tt.code = 'adult'
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,38 @@
+-- Правила цены кинотеатра. Сеанс без ручной цены (pricing_mode = 'dynamic')
+-- стоит столько, сколько дают подходящие правила, применённые по возрастанию
+-- priority к нулю; доплата за формат прибавляется после правил.
+-- Условия, оставленные пустыми, подходят под любой сеанс
+CREATE TABLE IF NOT EXISTS price_rule (
+    rule_id SERIAL PRIMARY KEY,
+    cinema_id INTEGER NOT NULL REFERENCES cinema (cinema_id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    valid_from DATE,
+    valid_to DATE,
+    weekdays INTEGER[] CHECK (cardinality(weekdays) > 0 AND weekdays <@ '{1,2,3,4,5,6,7}'),
+    time_from TIME,
+    time_to TIME,
+    premiere_weeks INTEGER CHECK (premiere_weeks > 0),
+    format VARCHAR(10) CHECK (format IN ('2d', '3d', 'imax', '4dx')),
+    seat_category VARCHAR(20) CHECK (seat_category IN ('standard', 'vip', 'sofa', 'wheelchair')),
+    min_occupancy NUMERIC(5, 2) CHECK (min_occupancy BETWEEN 0 AND 100),
+    action VARCHAR(10) NOT NULL CHECK (action IN ('set', 'percent', 'add')),
+    value NUMERIC(12, 2) NOT NULL,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    CHECK (valid_from IS NULL OR valid_to IS NULL OR valid_from <= valid_to),
+    CHECK ((time_from IS NULL) = (time_to IS NULL))
+);
+
+CREATE INDEX IF NOT EXISTS price_rule_cinema_idx ON price_rule (cinema_id, priority);
+
+-- Для динамических сеансов ticket_price — цена обычного места в пустом зале,
+-- пересчитывается при изменении правил
+ALTER TABLE session
+    ADD COLUMN IF NOT EXISTS pricing_mode VARCHAR(10) NOT NULL DEFAULT 'fixed'
+    CHECK (pricing_mode IN ('fixed', 'dynamic'));
+
+-- Места разных категорий в динамическом сеансе стоят по-разному,
+-- поэтому строк одного типа билета в продаже может быть несколько
+ALTER TABLE ticket_sale_item ADD COLUMN IF NOT EXISTS seat_category VARCHAR(20);
+ALTER TABLE ticket_sale_item DROP CONSTRAINT IF EXISTS ticket_sale_item_pkey;
+ALTER TABLE ticket_sale_item ADD COLUMN IF NOT EXISTS item_id SERIAL PRIMARY KEY;
@@ -0,0 +1,36 @@
+-- Занятые места сеанса: проданные билеты, активные и подтверждённые брони,
+-- ещё не истёкшие удержания. Общий расчёт для проверки вместимости и списков
+CREATE OR REPLACE FUNCTION session_occupancy(p_session_id INTEGER)
+RETURNS TABLE (sold INTEGER, booked INTEGER, held INTEGER)
+LANGUAGE sql STABLE AS $$
+    SELECT
+        (
+            SELECT COALESCE(SUM(ts.ticket_count), 0)
+            FROM ticket_sale ts
+            WHERE ts.session_id = p_session_id
+        )::int,
+        (
+            SELECT COALESCE(SUM(b.ticket_count), 0)
+            FROM booking b
+            WHERE b.session_id = p_session_id AND b.status IN ('active', 'completed')
+        )::int,
+        (
+            SELECT COALESCE(SUM(sh.ticket_count), 0)
+            FROM seat_hold sh
+            WHERE sh.session_id = p_session_id AND sh.expires_at > NOW()
+        )::int
+$$;
+
+-- Свободные места сеанса; NULL, если зал не задан и вместимость неизвестна
+CREATE OR REPLACE FUNCTION session_seats_free(p_session_id INTEGER)
+RETURNS INTEGER
+LANGUAGE sql STABLE AS $$
+    SELECT CASE
+        WHEN h.capacity IS NULL THEN NULL
+        ELSE GREATEST(h.capacity - o.sold - o.booked - o.held, 0)
+    END
+    FROM session s
+    LEFT JOIN hall h ON s.hall_id = h.hall_id
+    CROSS JOIN session_occupancy(s.session_id) o
+    WHERE s.session_id = p_session_id
+$$;
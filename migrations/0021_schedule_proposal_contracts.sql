@@ -0,0 +1,16 @@
+-- Минимум показов фильма в неделю по договору с дистрибьютором.
+-- Задаётся в заказе копии, 0 — договор минимума не требует
+ALTER TABLE film_order
+    ADD COLUMN IF NOT EXISTS min_screenings INTEGER NOT NULL DEFAULT 0
+    CHECK (min_screenings >= 0);
+
+-- Черновик без ticket_price планирует сеансы с ценой по правилам кинотеатра.
+-- Формат и звуковая дорожка черновика переходят в сеансы при утверждении
+ALTER TABLE schedule_proposal ALTER COLUMN ticket_price DROP NOT NULL;
+ALTER TABLE schedule_proposal
+    ADD COLUMN IF NOT EXISTS format VARCHAR(10) NOT NULL DEFAULT '2d'
+    CHECK (format IN ('2d', '3d', 'imax', '4dx'));
+ALTER TABLE schedule_proposal ADD COLUMN IF NOT EXISTS audio_language VARCHAR(3) NOT NULL DEFAULT 'ru';
+ALTER TABLE schedule_proposal ADD COLUMN IF NOT EXISTS subtitle_language VARCHAR(3);
+ALTER TABLE schedule_proposal
+    ADD COLUMN IF NOT EXISTS audio_description BOOLEAN NOT NULL DEFAULT FALSE;
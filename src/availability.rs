@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+use crate::errors::AppError;
+use crate::seats::{build_grid, fetch_seats};
+use crate::sessions::STATUS_SCHEDULED;
+
+pub const SEAT_FREE: &str = "free";
+pub const SEAT_HELD: &str = "held";
+pub const SEAT_BOOKED: &str = "booked";
+pub const SEAT_SOLD: &str = "sold";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatStateCell {
+    pub seat_id: i32,
+    pub seat_number: i32,
+    pub category: String,
+    pub state: String, // "free", "held", "booked", "sold"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatStateRow {
+    pub row_number: i32,
+    pub cells: Vec<Option<SeatStateCell>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AvailabilityResponse {
+    pub session_id: i32,
+    pub capacity: Option<i32>,
+    pub seats_sold: i32,
+    pub seats_booked: i32,
+    pub seats_held: i32,
+    pub seats_free: Option<i32>,
+    pub is_bookable: bool, // false для отменённых, уже начавшихся и заполненных сеансов
+    pub seat_map: Option<Vec<SeatStateRow>>,
+}
+
+// Заполненность сеанса: проданные билеты, места под активными и
+// подтверждёнными бронями (подтверждение не создаёт продажу, места остаются
+// за бронью) и под ещё не истёкшими удержаниями.
+// Вместимость известна только для сеансов с залом
+#[derive(Debug)]
+pub struct Occupancy {
+    pub capacity: Option<i32>,
+    pub sold: i32,
+    pub booked: i32,
+    pub held: i32,
+}
+
+impl Occupancy {
+    pub fn remaining(&self) -> Option<i32> {
+        self.capacity
+            .map(|capacity| (capacity - self.sold - self.booked - self.held).max(0))
+    }
+}
+
+pub async fn fetch_occupancy<'e>(
+    executor: impl PgExecutor<'e>,
+    session_id: i32,
+) -> Result<Occupancy, AppError> {
+    let row = sqlx::query!(
+        r#"
+        SELECT
+            h.capacity as "capacity?",
+            o.sold as "sold!",
+            o.booked as "booked!",
+            o.held as "held!"
+        FROM session s
+        LEFT JOIN hall h ON s.hall_id = h.hall_id
+        CROSS JOIN session_occupancy(s.session_id) o
+        WHERE s.session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    Ok(Occupancy {
+        capacity: row.capacity,
+        sold: row.sold,
+        booked: row.booked,
+        held: row.held,
+    })
+}
+
+// Проверяет, что на сеансе осталось не меньше requested мест.
+// Вызывается внутри транзакции: строка сеанса блокируется до её конца,
+// поэтому параллельные брони и продажи на тот же сеанс проверяются по очереди.
+// На отменённый сеанс места не выдаются
+pub async fn ensure_capacity(
+    conn: &mut PgConnection,
+    session_id: i32,
+    requested: i32,
+) -> Result<(), AppError> {
+    let status = sqlx::query_scalar!(
+        "SELECT status FROM session WHERE session_id = $1 FOR UPDATE",
+        session_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    if status != STATUS_SCHEDULED {
+        return Err(AppError::Conflict("Session is cancelled".into()));
+    }
+
+    let occupancy = fetch_occupancy(&mut *conn, session_id).await?;
+
+    match occupancy.remaining() {
+        Some(remaining) if remaining < requested => Err(AppError::SoldOut { remaining }),
+        _ => Ok(()),
+    }
+}
+
+pub async fn get_session_availability(
+    pool: web::Data<PgPool>,
+    session_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = session_id.into_inner();
+
+    let occupancy = fetch_occupancy(pool.get_ref(), session_id).await?;
+
+    let session = sqlx::query!(
+        r#"
+        SELECT hall_id, status, start_time > NOW() as "upcoming!"
+        FROM session
+        WHERE session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let seats = match session.hall_id {
+        Some(hall_id) => fetch_seats(pool.get_ref(), hall_id).await?,
+        None => Vec::new(),
+    };
+
+    let seat_map = if seats.is_empty() {
+        None
+    } else {
+        let taken: HashMap<i32, &str> = sqlx::query!(
+            r#"
+            SELECT ss.seat_id, ss.sale_id, ss.booking_id
+            FROM session_seat ss
+            LEFT JOIN seat_hold sh ON ss.hold_id = sh.hold_id
+            WHERE ss.session_id = $1
+            AND (ss.hold_id IS NULL OR sh.expires_at > NOW())
+            "#,
+            session_id
+        )
+        .fetch_all(pool.get_ref())
+        .await?
+        .into_iter()
+        .map(|row| {
+            let state = if row.sale_id.is_some() {
+                SEAT_SOLD
+            } else if row.booking_id.is_some() {
+                SEAT_BOOKED
+            } else {
+                SEAT_HELD
+            };
+            (row.seat_id, state)
+        })
+        .collect();
+
+        let (_, rows) = build_grid(&seats, |s| SeatStateCell {
+            seat_id: s.seat_id,
+            seat_number: s.seat_number,
+            category: s.category.clone(),
+            state: taken
+                .get(&s.seat_id)
+                .copied()
+                .unwrap_or(SEAT_FREE)
+                .to_string(),
+        });
+
+        Some(
+            rows.into_iter()
+                .map(|(row_number, cells)| SeatStateRow { row_number, cells })
+                .collect(),
+        )
+    };
+
+    let seats_free = occupancy.remaining();
+    // Те же условия, что проверяет ensure_capacity, плюс сеанс ещё не начался
+    let is_bookable = session.status == STATUS_SCHEDULED
+        && session.upcoming
+        && seats_free.is_none_or(|free| free > 0);
+
+    Ok(HttpResponse::Ok().json(AvailabilityResponse {
+        session_id,
+        capacity: occupancy.capacity,
+        seats_sold: occupancy.sold,
+        seats_booked: occupancy.booked,
+        seats_held: occupancy.held,
+        seats_free,
+        is_bookable,
+        seat_map,
+    }))
+}
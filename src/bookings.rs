@@ -1,8 +1,14 @@
 use actix_web::{HttpResponse, web};
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 
+use crate::availability::ensure_capacity;
 use crate::errors::AppError;
+use crate::holds::consume_hold;
+use crate::seats::{
+    SeatHolder, WithSeats, assign_seats, fetch_assigned_seats, resolve_ticket_count,
+};
+use crate::ticket_types::{TicketLineRequest, requested_count, resolve_ticket_lines};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BookingResponse {
@@ -29,7 +35,47 @@ pub struct BookingWithDetailsResponse {
 pub struct CreateBookingRequest {
     pub session_id: i32,
     pub customer_id: i32,
-    pub ticket_count: i32,
+    pub ticket_count: Option<i32>, // для сеансов со свободной рассадкой
+    pub seat_ids: Option<Vec<i32>>, // для залов со схемой мест
+    pub hold_id: Option<i32>,      // удержание, по которому оформляется заказ
+    pub tickets: Option<Vec<TicketLineRequest>>, // состав по типам, по умолчанию все взрослые
+}
+
+// Сколько билетов какого типа забронировано; цена считается при продаже
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BookingItemResponse {
+    pub ticket_type_id: i32,
+    pub code: String,
+    pub name: String,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WithItems<T> {
+    #[serde(flatten)]
+    pub inner: T,
+    pub items: Vec<BookingItemResponse>,
+}
+
+async fn fetch_booking_items<'e>(
+    executor: impl PgExecutor<'e>,
+    booking_id: i32,
+) -> Result<Vec<BookingItemResponse>, AppError> {
+    let items = sqlx::query_as!(
+        BookingItemResponse,
+        r#"
+        SELECT i.ticket_type_id, tt.code, tt.name, i.quantity
+        FROM booking_item i
+        JOIN ticket_type tt ON i.ticket_type_id = tt.ticket_type_id
+        WHERE i.booking_id = $1
+        ORDER BY i.ticket_type_id
+        "#,
+        booking_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(items)
 }
 
 pub async fn get_bookings(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
@@ -41,7 +87,7 @@ pub async fn get_bookings(pool: web::Data<PgPool>) -> Result<HttpResponse, AppEr
             session_id,
             customer_id,
             ticket_count,
-            booking_time::text,
+            to_local_iso(booking_time, session_timezone(session_id)) as "booking_time!",
             status
         FROM booking
         "#
@@ -60,7 +106,7 @@ pub async fn get_active_bookings(pool: web::Data<PgPool>) -> Result<HttpResponse
             b.booking_id,
             f.title as film_title,
             c.name as cinema_name,
-            s.start_time::text,
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
             (cust.first_name || ' ' || cust.last_name) as customer_name,
             b.ticket_count,
             b.status
@@ -84,6 +130,8 @@ pub async fn get_booking(
     pool: web::Data<PgPool>,
     booking_id: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
+    let booking_id = booking_id.into_inner();
+
     let booking = sqlx::query_as!(
         BookingWithDetailsResponse,
         r#"
@@ -91,7 +139,7 @@ pub async fn get_booking(
             b.booking_id,
             f.title as film_title,
             c.name as cinema_name,
-            s.start_time::text,
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
             (cust.first_name || ' ' || cust.last_name) as customer_name,
             b.ticket_count,
             b.status
@@ -102,21 +150,53 @@ pub async fn get_booking(
         JOIN customer cust ON b.customer_id = cust.customer_id
         WHERE b.booking_id = $1
         "#,
-        booking_id.into_inner()
+        booking_id
     )
     .fetch_optional(pool.get_ref())
-    .await?;
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".into()))?;
 
-    match booking {
-        Some(b) => Ok(HttpResponse::Ok().json(b)),
-        None => Err(AppError::NotFound("Booking not found".into())),
-    }
+    let items = fetch_booking_items(pool.get_ref(), booking_id).await?;
+    let seats = fetch_assigned_seats(pool.get_ref(), SeatHolder::Booking(booking_id)).await?;
+
+    Ok(HttpResponse::Ok().json(WithSeats {
+        inner: WithItems {
+            inner: booking,
+            items,
+        },
+        seats,
+    }))
 }
 
 pub async fn create_booking(
     pool: web::Data<PgPool>,
     new_booking: web::Json<CreateBookingRequest>,
 ) -> Result<HttpResponse, AppError> {
+    let mut seat_ids = new_booking.seat_ids.clone().unwrap_or_default();
+
+    let mut tx = pool.begin().await?;
+
+    let lines = new_booking.tickets.as_deref();
+    let mut requested = new_booking.ticket_count.or(requested_count(lines));
+
+    if let Some(hold_id) = new_booking.hold_id {
+        let held = consume_hold(
+            &mut tx,
+            hold_id,
+            new_booking.session_id,
+            new_booking.customer_id,
+        )
+        .await?;
+        held.fill_request(&mut seat_ids, &mut requested)?;
+    }
+
+    let ticket_count =
+        resolve_ticket_count(&mut tx, new_booking.session_id, requested, &seat_ids).await?;
+
+    ensure_capacity(&mut tx, new_booking.session_id, ticket_count).await?;
+    let ticket_lines =
+        resolve_ticket_lines(&mut tx, new_booking.session_id, lines, ticket_count).await?;
+
     let booking = sqlx::query_as!(
         BookingResponse,
         r#"
@@ -127,52 +207,114 @@ pub async fn create_booking(
             session_id,
             customer_id,
             ticket_count,
-            booking_time::text,
+            to_local_iso(booking_time, session_timezone(session_id)) as "booking_time!",
             status
         "#,
         new_booking.session_id,
         new_booking.customer_id,
-        new_booking.ticket_count
+        ticket_count
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(HttpResponse::Created().json(booking))
+    let type_ids: Vec<i32> = ticket_lines.iter().map(|l| l.ticket_type_id).collect();
+    let quantities: Vec<i32> = ticket_lines.iter().map(|l| l.quantity).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO booking_item (booking_id, ticket_type_id, quantity)
+        SELECT $1, * FROM UNNEST($2::int[], $3::int[])
+        "#,
+        booking.booking_id,
+        &type_ids,
+        &quantities
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let items = fetch_booking_items(&mut *tx, booking.booking_id).await?;
+
+    let seats = assign_seats(
+        &mut tx,
+        new_booking.session_id,
+        &seat_ids,
+        SeatHolder::Booking(booking.booking_id),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(WithSeats {
+        inner: WithItems {
+            inner: booking,
+            items,
+        },
+        seats,
+    }))
 }
 
+// Подтвердить можно только активную бронь. Сеанс блокируется так же, как
+// при бронировании, поэтому бронь не подтвердится на отменённый сеанс
+// и не разойдётся с параллельной отменой
 pub async fn confirm_booking(
     pool: web::Data<PgPool>,
     booking_id: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
+    let booking_id = booking_id.into_inner();
+
+    let mut tx = pool.begin().await?;
+
+    let current = sqlx::query!(
+        "SELECT session_id, status FROM booking WHERE booking_id = $1",
+        booking_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".into()))?;
+
+    if current.status != "active" {
+        return Err(AppError::Conflict(format!(
+            "Booking is {} and cannot be confirmed",
+            current.status
+        )));
+    }
+
+    // Активная бронь уже учтена в заполненности, новых мест не требуется
+    ensure_capacity(&mut tx, current.session_id, 0).await?;
+
     let booking = sqlx::query_as!(
         BookingResponse,
         r#"
         UPDATE booking
         SET status = 'completed'
-        WHERE booking_id = $1
+        WHERE booking_id = $1 AND status = 'active'
         RETURNING
             booking_id,
             session_id,
             customer_id,
             ticket_count,
-            booking_time::text,
+            to_local_iso(booking_time, session_timezone(session_id)) as "booking_time!",
             status
         "#,
-        booking_id.into_inner()
+        booking_id
     )
-    .fetch_optional(pool.get_ref())
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Booking is no longer active".into()))?;
 
-    match booking {
-        Some(b) => Ok(HttpResponse::Ok().json(b)),
-        None => Err(AppError::NotFound("Booking not found".into())),
-    }
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(booking))
 }
 
 pub async fn cancel_booking(
     pool: web::Data<PgPool>,
     booking_id: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
+    let booking_id = booking_id.into_inner();
+
+    let mut tx = pool.begin().await?;
+
     let booking = sqlx::query_as!(
         BookingResponse,
         r#"
@@ -184,16 +326,21 @@ pub async fn cancel_booking(
             session_id,
             customer_id,
             ticket_count,
-            booking_time::text,
+            to_local_iso(booking_time, session_timezone(session_id)) as "booking_time!",
             status
         "#,
-        booking_id.into_inner()
+        booking_id
     )
-    .fetch_optional(pool.get_ref())
-    .await?;
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Booking not found".into()))?;
 
-    match booking {
-        Some(b) => Ok(HttpResponse::Ok().json(b)),
-        None => Err(AppError::NotFound("Booking not found".into())),
-    }
+    // Места отменённой брони снова свободны
+    sqlx::query!("DELETE FROM session_seat WHERE booking_id = $1", booking_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(booking))
 }
@@ -4,6 +4,8 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
 use crate::errors::AppError;
+use crate::money::{DEFAULT_CURRENCY, cinema_currency, validate_currency_code};
+use crate::ticket_types::create_default_ticket_types;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CinemaResponse {
@@ -14,16 +16,71 @@ pub struct CinemaResponse {
     pub hall_count: i32,
     pub opening_time: String,
     pub closing_time: String,
+    pub ad_minutes: i32,
+    pub cleaning_minutes: i32,
+    pub timezone: String,
+    pub currency: String,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateCinemaRequest {
     pub name: String,
     pub address: String,
-    pub employee_count: i32,
-    pub hall_count: i32,
     pub opening_time: String,
     pub closing_time: String,
+    pub ad_minutes: Option<i32>,       // реклама и трейлеры перед фильмом
+    pub cleaning_minutes: Option<i32>, // уборка зала после сеанса
+    pub timezone: Option<String>,      // IANA, по умолчанию "Europe/Moscow"
+    pub currency: Option<String>,      // ISO 4217, по умолчанию "RUB"
+}
+
+fn validate_buffers(cinema: &CreateCinemaRequest) -> Result<(), AppError> {
+    if cinema.ad_minutes.is_some_and(|m| m < 0) || cinema.cleaning_minutes.is_some_and(|m| m < 0) {
+        return Err(AppError::InvalidInput(
+            "Ad and cleaning minutes cannot be negative".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Часовой пояс проверяется по списку, который знает Postgres,
+// потому что именно он переводит время сеансов в местное
+async fn validate_timezone(pool: &PgPool, timezone: Option<&str>) -> Result<(), AppError> {
+    let Some(timezone) = timezone else {
+        return Ok(());
+    };
+
+    let known = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM pg_timezone_names WHERE name = $1) as "exists!""#,
+        timezone
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !known {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown timezone {}",
+            timezone
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn ensure_cinema_exists(pool: &PgPool, cinema_id: i32) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM cinema WHERE cinema_id = $1) as "exists!""#,
+        cinema_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        return Err(AppError::NotFound("Cinema not found".into()));
+    }
+
+    Ok(())
 }
 
 pub async fn get_cinemas(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
@@ -34,10 +91,14 @@ pub async fn get_cinemas(pool: web::Data<PgPool>) -> Result<HttpResponse, AppErr
             cinema_id,
             name,
             address,
-            employee_count,
-            hall_count,
+            cinema_employee_count(cinema.cinema_id) as "employee_count!",
+            cinema_hall_count(cinema.cinema_id) as "hall_count!",
             opening_time::text,
-            closing_time::text
+            closing_time::text,
+            ad_minutes,
+            cleaning_minutes,
+            timezone,
+            currency::text as "currency!"
         FROM cinema
         "#
     )
@@ -58,10 +119,14 @@ pub async fn get_cinema(
             cinema_id,
             name,
             address,
-            employee_count,
-            hall_count,
+            cinema_employee_count(cinema.cinema_id) as "employee_count!",
+            cinema_hall_count(cinema.cinema_id) as "hall_count!",
             opening_time::text,
-            closing_time::text
+            closing_time::text,
+            ad_minutes,
+            cleaning_minutes,
+            timezone,
+            currency::text as "currency!"
         FROM cinema
         WHERE cinema_id = $1
         "#,
@@ -82,31 +147,65 @@ pub async fn create_cinema(
 ) -> Result<HttpResponse, AppError> {
     let opening_time = NaiveTime::parse_from_str(&new_cinema.opening_time, "%H:%M:%S")?;
     let closing_time = NaiveTime::parse_from_str(&new_cinema.closing_time, "%H:%M:%S")?;
+    validate_buffers(&new_cinema)?;
+    validate_timezone(pool.get_ref(), new_cinema.timezone.as_deref()).await?;
+    let currency = new_cinema.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+    validate_currency_code(currency)?;
+
+    let mut tx = pool.begin().await?;
 
     let cinema = sqlx::query_as!(
         CinemaResponse,
         r#"
-        INSERT INTO cinema (name, address, employee_count, hall_count, opening_time, closing_time)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO cinema (
+            name,
+            address,
+            opening_time,
+            closing_time,
+            ad_minutes,
+            cleaning_minutes,
+            timezone,
+            currency
+        )
+        VALUES (
+            $1,
+            $2,
+            $3,
+            $4,
+            COALESCE($5, 15),
+            COALESCE($6, 15),
+            COALESCE($7, 'Europe/Moscow'),
+            $8
+        )
         RETURNING
             cinema_id,
             name,
             address,
-            employee_count,
-            hall_count,
+            cinema_employee_count(cinema.cinema_id) as "employee_count!",
+            cinema_hall_count(cinema.cinema_id) as "hall_count!",
             opening_time::text,
-            closing_time::text
+            closing_time::text,
+            ad_minutes,
+            cleaning_minutes,
+            timezone,
+            currency::text as "currency!"
         "#,
         new_cinema.name,
         new_cinema.address,
-        new_cinema.employee_count,
-        new_cinema.hall_count,
         opening_time,
-        closing_time
+        closing_time,
+        new_cinema.ad_minutes,
+        new_cinema.cleaning_minutes,
+        new_cinema.timezone,
+        currency
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await?;
 
+    create_default_ticket_types(&mut tx, cinema.cinema_id).await?;
+
+    tx.commit().await?;
+
     Ok(HttpResponse::Created().json(cinema))
 }
 
@@ -117,6 +216,18 @@ pub async fn update_cinema(
 ) -> Result<HttpResponse, AppError> {
     let opening_time = NaiveTime::parse_from_str(&updated_cinema.opening_time, "%H:%M:%S")?;
     let closing_time = NaiveTime::parse_from_str(&updated_cinema.closing_time, "%H:%M:%S")?;
+    validate_buffers(&updated_cinema)?;
+    validate_timezone(pool.get_ref(), updated_cinema.timezone.as_deref()).await?;
+    let cinema_id = cinema_id.into_inner();
+
+    // Валюту нельзя сменить: все цены и выручка кинотеатра записаны в ней
+    if let Some(currency) = &updated_cinema.currency
+        && *currency != cinema_currency(pool.get_ref(), cinema_id).await?
+    {
+        return Err(AppError::Conflict(
+            "Cinema currency cannot be changed".into(),
+        ));
+    }
 
     let cinema = sqlx::query_as!(
         CinemaResponse,
@@ -125,27 +236,33 @@ pub async fn update_cinema(
         SET
             name = $1,
             address = $2,
-            employee_count = $3,
-            hall_count = $4,
-            opening_time = $5,
-            closing_time = $6
-        WHERE cinema_id = $7
+            opening_time = $3,
+            closing_time = $4,
+            ad_minutes = COALESCE($5, ad_minutes),
+            cleaning_minutes = COALESCE($6, cleaning_minutes),
+            timezone = COALESCE($7, timezone)
+        WHERE cinema_id = $8
         RETURNING
             cinema_id,
             name,
             address,
-            employee_count,
-            hall_count,
+            cinema_employee_count(cinema.cinema_id) as "employee_count!",
+            cinema_hall_count(cinema.cinema_id) as "hall_count!",
             opening_time::text,
-            closing_time::text
+            closing_time::text,
+            ad_minutes,
+            cleaning_minutes,
+            timezone,
+            currency::text as "currency!"
         "#,
         updated_cinema.name,
         updated_cinema.address,
-        updated_cinema.employee_count,
-        updated_cinema.hall_count,
         opening_time,
         closing_time,
-        cinema_id.into_inner()
+        updated_cinema.ad_minutes,
+        updated_cinema.cleaning_minutes,
+        updated_cinema.timezone,
+        cinema_id
     )
     .fetch_optional(pool.get_ref())
     .await?;
@@ -0,0 +1,256 @@
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::errors::AppError;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CustomerResponse {
+    pub customer_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub phone: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomerRequest {
+    pub first_name: String,
+    pub last_name: String,
+    pub phone: String,
+}
+
+// Приводит номер телефона к формату E.164 (+79991234567).
+// Номера без кода страны считаются российскими.
+pub fn normalize_phone(raw: &str) -> Result<String, AppError> {
+    let trimmed = raw.trim();
+    let has_plus = trimmed.starts_with('+');
+
+    if trimmed
+        .chars()
+        .skip(if has_plus { 1 } else { 0 })
+        .any(|c| !(c.is_ascii_digit() || matches!(c, ' ' | '-' | '(' | ')')))
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid phone number: {}",
+            raw
+        )));
+    }
+
+    let digits: String = trimmed.chars().filter(|c| c.is_ascii_digit()).collect();
+
+    let digits = if has_plus {
+        digits
+    } else if digits.len() == 11 && digits.starts_with('8') {
+        format!("7{}", &digits[1..])
+    } else if digits.len() == 10 {
+        format!("7{}", digits)
+    } else {
+        digits
+    };
+
+    if digits.len() < 8 || digits.len() > 15 || digits.starts_with('0') {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid phone number: {}",
+            raw
+        )));
+    }
+
+    Ok(format!("+{}", digits))
+}
+
+// Проверка даёт понятное сообщение с номером владельца; от гонки двух запросов
+// защищает уникальный индекс customer_phone_key
+async fn ensure_phone_is_free(
+    pool: &PgPool,
+    phone: &str,
+    customer_id: Option<i32>,
+) -> Result<(), AppError> {
+    let existing = sqlx::query_scalar!(
+        r#"
+        SELECT customer_id
+        FROM customer
+        WHERE phone = $1
+        AND ($2::int IS NULL OR customer_id <> $2)
+        "#,
+        phone,
+        customer_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match existing {
+        Some(id) => Err(AppError::Conflict(format!(
+            "Phone {} is already registered to customer {}",
+            phone, id
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub async fn get_customers(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let customers = sqlx::query_as!(
+        CustomerResponse,
+        r#"
+        SELECT
+            customer_id,
+            first_name,
+            last_name,
+            phone
+        FROM customer
+        ORDER BY last_name, first_name
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(customers))
+}
+
+pub async fn get_customer(
+    pool: web::Data<PgPool>,
+    customer_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let customer = sqlx::query_as!(
+        CustomerResponse,
+        r#"
+        SELECT
+            customer_id,
+            first_name,
+            last_name,
+            phone
+        FROM customer
+        WHERE customer_id = $1
+        "#,
+        customer_id.into_inner()
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match customer {
+        Some(c) => Ok(HttpResponse::Ok().json(c)),
+        None => Err(AppError::NotFound("Customer not found".into())),
+    }
+}
+
+pub async fn get_customer_by_phone(
+    pool: web::Data<PgPool>,
+    phone: web::Path<String>,
+) -> Result<HttpResponse, AppError> {
+    let phone = normalize_phone(&phone.into_inner())?;
+
+    let customer = sqlx::query_as!(
+        CustomerResponse,
+        r#"
+        SELECT
+            customer_id,
+            first_name,
+            last_name,
+            phone
+        FROM customer
+        WHERE phone = $1
+        "#,
+        phone
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match customer {
+        Some(c) => Ok(HttpResponse::Ok().json(c)),
+        None => Err(AppError::NotFound("Customer not found".into())),
+    }
+}
+
+pub async fn create_customer(
+    pool: web::Data<PgPool>,
+    new_customer: web::Json<CreateCustomerRequest>,
+) -> Result<HttpResponse, AppError> {
+    let phone = normalize_phone(&new_customer.phone)?;
+    ensure_phone_is_free(pool.get_ref(), &phone, None).await?;
+
+    let customer = sqlx::query_as!(
+        CustomerResponse,
+        r#"
+        INSERT INTO customer (first_name, last_name, phone)
+        VALUES ($1, $2, $3)
+        RETURNING
+            customer_id,
+            first_name,
+            last_name,
+            phone
+        "#,
+        new_customer.first_name,
+        new_customer.last_name,
+        phone
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(AppError::on_unique_violation(format!(
+        "Phone {} is already registered",
+        phone
+    )))?;
+
+    Ok(HttpResponse::Created().json(customer))
+}
+
+pub async fn update_customer(
+    pool: web::Data<PgPool>,
+    customer_id: web::Path<i32>,
+    updated_customer: web::Json<CreateCustomerRequest>,
+) -> Result<HttpResponse, AppError> {
+    let customer_id = customer_id.into_inner();
+    let phone = normalize_phone(&updated_customer.phone)?;
+    ensure_phone_is_free(pool.get_ref(), &phone, Some(customer_id)).await?;
+
+    let customer = sqlx::query_as!(
+        CustomerResponse,
+        r#"
+        UPDATE customer
+        SET
+            first_name = $1,
+            last_name = $2,
+            phone = $3
+        WHERE customer_id = $4
+        RETURNING
+            customer_id,
+            first_name,
+            last_name,
+            phone
+        "#,
+        updated_customer.first_name,
+        updated_customer.last_name,
+        phone,
+        customer_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(AppError::on_unique_violation(format!(
+        "Phone {} is already registered",
+        phone
+    )))?;
+
+    match customer {
+        Some(c) => Ok(HttpResponse::Ok().json(c)),
+        None => Err(AppError::NotFound("Customer not found".into())),
+    }
+}
+
+pub async fn delete_customer(
+    pool: web::Data<PgPool>,
+    customer_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM customer WHERE customer_id = $1",
+        customer_id.into_inner()
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(AppError::on_foreign_key_violation(
+        "Customer has bookings or ticket sales and cannot be deleted".into(),
+    ))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Customer not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
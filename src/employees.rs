@@ -0,0 +1,314 @@
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmployeeResponse {
+    pub employee_id: i32,
+    pub cinema_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub position: String,
+    pub age: i32,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateEmployeeRequest {
+    pub cinema_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub position: String,
+    pub age: i32,
+}
+
+// Данные сотрудника без привязки к кинотеатру: для обновления
+// и для создания через /cinemas/{id}/employees
+#[derive(Debug, Deserialize)]
+pub struct EmployeeDetailsRequest {
+    pub first_name: String,
+    pub last_name: String,
+    pub position: String,
+    pub age: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransferEmployeeRequest {
+    pub cinema_id: i32,
+}
+
+// Минимальный возраст найма задаёт трудовое право, а не это API:
+// здесь отсекаются только заведомо неверные значения
+fn validate_age(age: i32) -> Result<(), AppError> {
+    if age <= 0 {
+        return Err(AppError::InvalidInput(
+            "Employee age must be positive".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn insert_employee(
+    pool: &PgPool,
+    cinema_id: i32,
+    details: &EmployeeDetailsRequest,
+) -> Result<EmployeeResponse, AppError> {
+    validate_age(details.age)?;
+
+    ensure_cinema_exists(pool, cinema_id).await?;
+
+    let employee = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        INSERT INTO employee (cinema_id, first_name, last_name, position, age, is_active)
+        VALUES ($1, $2, $3, $4, $5, TRUE)
+        RETURNING
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        "#,
+        cinema_id,
+        details.first_name,
+        details.last_name,
+        details.position,
+        details.age
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(employee)
+}
+
+async fn find_employee(pool: &PgPool, employee_id: i32) -> Result<EmployeeResponse, AppError> {
+    let employee = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        SELECT
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        FROM employee
+        WHERE employee_id = $1
+        "#,
+        employee_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    employee.ok_or_else(|| AppError::NotFound("Employee not found".into()))
+}
+
+pub async fn get_employees(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let employees = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        SELECT
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        FROM employee
+        ORDER BY cinema_id, last_name, first_name
+        "#
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(employees))
+}
+
+pub async fn get_cinema_employees(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let employees = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        SELECT
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        FROM employee
+        WHERE cinema_id = $1
+        ORDER BY last_name, first_name
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(employees))
+}
+
+pub async fn get_employee(
+    pool: web::Data<PgPool>,
+    employee_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let employee = find_employee(pool.get_ref(), employee_id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(employee))
+}
+
+pub async fn create_employee(
+    pool: web::Data<PgPool>,
+    new_employee: web::Json<CreateEmployeeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let new_employee = new_employee.into_inner();
+    let details = EmployeeDetailsRequest {
+        first_name: new_employee.first_name,
+        last_name: new_employee.last_name,
+        position: new_employee.position,
+        age: new_employee.age,
+    };
+
+    let employee = insert_employee(pool.get_ref(), new_employee.cinema_id, &details).await?;
+
+    Ok(HttpResponse::Created().json(employee))
+}
+
+pub async fn create_cinema_employee(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    new_employee: web::Json<EmployeeDetailsRequest>,
+) -> Result<HttpResponse, AppError> {
+    let employee = insert_employee(pool.get_ref(), cinema_id.into_inner(), &new_employee).await?;
+
+    Ok(HttpResponse::Created().json(employee))
+}
+
+pub async fn update_employee(
+    pool: web::Data<PgPool>,
+    employee_id: web::Path<i32>,
+    updated_employee: web::Json<EmployeeDetailsRequest>,
+) -> Result<HttpResponse, AppError> {
+    validate_age(updated_employee.age)?;
+
+    let employee = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        UPDATE employee
+        SET
+            first_name = $1,
+            last_name = $2,
+            position = $3,
+            age = $4
+        WHERE employee_id = $5
+        RETURNING
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        "#,
+        updated_employee.first_name,
+        updated_employee.last_name,
+        updated_employee.position,
+        updated_employee.age,
+        employee_id.into_inner()
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match employee {
+        Some(e) => Ok(HttpResponse::Ok().json(e)),
+        None => Err(AppError::NotFound("Employee not found".into())),
+    }
+}
+
+pub async fn transfer_employee(
+    pool: web::Data<PgPool>,
+    employee_id: web::Path<i32>,
+    transfer: web::Json<TransferEmployeeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let employee = find_employee(pool.get_ref(), employee_id.into_inner()).await?;
+
+    if !employee.is_active {
+        return Err(AppError::InvalidInput(
+            "Cannot transfer a deactivated employee".into(),
+        ));
+    }
+    if employee.cinema_id == transfer.cinema_id {
+        return Err(AppError::InvalidInput(
+            "Employee already works at this cinema".into(),
+        ));
+    }
+
+    ensure_cinema_exists(pool.get_ref(), transfer.cinema_id).await?;
+
+    let employee = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        UPDATE employee
+        SET cinema_id = $1
+        WHERE employee_id = $2
+        RETURNING
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        "#,
+        transfer.cinema_id,
+        employee.employee_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(employee))
+}
+
+pub async fn deactivate_employee(
+    pool: web::Data<PgPool>,
+    employee_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let employee = sqlx::query_as!(
+        EmployeeResponse,
+        r#"
+        UPDATE employee
+        SET is_active = FALSE
+        WHERE employee_id = $1
+        RETURNING
+            employee_id,
+            cinema_id,
+            first_name,
+            last_name,
+            position,
+            age,
+            is_active
+        "#,
+        employee_id.into_inner()
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match employee {
+        Some(e) => Ok(HttpResponse::Ok().json(e)),
+        None => Err(AppError::NotFound("Employee not found".into())),
+    }
+}
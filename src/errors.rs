@@ -1,4 +1,5 @@
 use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
 use sqlx::error::Error as SqlxError;
 use std::fmt;
 
@@ -7,6 +8,14 @@ pub enum AppError {
     DbError(SqlxError),
     NotFound(String),
     InvalidInput(String),
+    Conflict(String),
+    SoldOut { remaining: i32 },
+}
+
+#[derive(Serialize)]
+struct SoldOutBody {
+    message: &'static str,
+    remaining: i32,
 }
 
 impl fmt::Display for AppError {
@@ -15,6 +24,10 @@ impl fmt::Display for AppError {
             AppError::DbError(err) => write!(f, "Database error: {}", err),
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
             AppError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
+            AppError::Conflict(msg) => write!(f, "Conflict: {}", msg),
+            AppError::SoldOut { remaining } => {
+                write!(f, "Sold out: {} seats remaining", remaining)
+            }
         }
     }
 }
@@ -27,6 +40,30 @@ impl ResponseError for AppError {
             }
             AppError::NotFound(msg) => HttpResponse::NotFound().json(msg),
             AppError::InvalidInput(msg) => HttpResponse::BadRequest().json(msg),
+            AppError::Conflict(msg) => HttpResponse::Conflict().json(msg),
+            AppError::SoldOut { remaining } => HttpResponse::Conflict().json(SoldOutBody {
+                message: "Sold out",
+                remaining: *remaining,
+            }),
+        }
+    }
+}
+
+impl AppError {
+    // Нарушение уникальности (SQLSTATE 23505) — например, когда два запроса
+    // одновременно прошли проверку и вставляют одно и то же — это 409, а не 500
+    pub fn on_unique_violation(message: String) -> impl FnOnce(SqlxError) -> AppError {
+        move |err| match &err {
+            SqlxError::Database(db) if db.is_unique_violation() => AppError::Conflict(message),
+            _ => AppError::DbError(err),
+        }
+    }
+
+    // Нарушение внешнего ключа (SQLSTATE 23503): удаляемую запись ещё используют
+    pub fn on_foreign_key_violation(message: String) -> impl FnOnce(SqlxError) -> AppError {
+        move |err| match &err {
+            SqlxError::Database(db) if db.is_foreign_key_violation() => AppError::Conflict(message),
+            _ => AppError::DbError(err),
         }
     }
 }
@@ -0,0 +1,302 @@
+use actix_web::{HttpResponse, web};
+use chrono::NaiveDate;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+use crate::films::ensure_film_exists;
+use crate::money::{ensure_cinema_currency, validate_amount};
+
+// Жизненный цикл заказа: ordered -> delivered -> returned,
+// отменить можно только ещё не доставленный заказ
+pub const STATUS_ORDERED: &str = "ordered";
+pub const STATUS_DELIVERED: &str = "delivered";
+pub const STATUS_RETURNED: &str = "returned";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FilmOrderResponse {
+    pub order_id: i32,
+    pub film_id: i32,
+    pub cinema_id: i32,
+    pub employee_id: i32,
+    pub delivery_date: String,
+    pub rental_cost: Decimal,
+    pub currency: String,
+    pub min_screenings: i32, // минимум показов в неделю по договору
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateFilmOrderRequest {
+    pub film_id: i32,
+    pub cinema_id: i32,
+    pub employee_id: i32,
+    pub delivery_date: String,
+    pub rental_cost: Decimal,
+    pub currency: String,            // должна совпадать с валютой кинотеатра
+    pub min_screenings: Option<i32>, // по умолчанию 0 — без обязательного минимума
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FilmOrderFilter {
+    pub cinema_id: Option<i32>,
+    pub film_id: Option<i32>,
+    pub status: Option<String>,
+    pub from: Option<String>, // Формат "YYYY-MM-DD"
+    pub to: Option<String>,   // Формат "YYYY-MM-DD"
+}
+
+fn parse_optional_date(value: &Option<String>) -> Result<Option<NaiveDate>, AppError> {
+    value
+        .as_deref()
+        .map(|v| NaiveDate::parse_from_str(v, "%Y-%m-%d"))
+        .transpose()
+        .map_err(|_| AppError::InvalidInput("Dates must be in YYYY-MM-DD format".into()))
+}
+
+pub async fn get_film_orders(
+    pool: web::Data<PgPool>,
+    filter: web::Query<FilmOrderFilter>,
+) -> Result<HttpResponse, AppError> {
+    let from = parse_optional_date(&filter.from)?;
+    let to = parse_optional_date(&filter.to)?;
+
+    let orders = sqlx::query_as!(
+        FilmOrderResponse,
+        r#"
+        SELECT
+            order_id,
+            film_id,
+            cinema_id,
+            employee_id,
+            delivery_date::text,
+            rental_cost,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = film_order.cinema_id
+            ) as "currency!",
+            min_screenings,
+            status
+        FROM film_order
+        WHERE ($1::int IS NULL OR cinema_id = $1)
+        AND ($2::int IS NULL OR film_id = $2)
+        AND ($3::text IS NULL OR status = $3)
+        AND ($4::date IS NULL OR delivery_date >= $4)
+        AND ($5::date IS NULL OR delivery_date <= $5)
+        ORDER BY delivery_date, order_id
+        "#,
+        filter.cinema_id,
+        filter.film_id,
+        filter.status,
+        from,
+        to
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(orders))
+}
+
+pub async fn get_film_order(
+    pool: web::Data<PgPool>,
+    order_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let order = sqlx::query_as!(
+        FilmOrderResponse,
+        r#"
+        SELECT
+            order_id,
+            film_id,
+            cinema_id,
+            employee_id,
+            delivery_date::text,
+            rental_cost,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = film_order.cinema_id
+            ) as "currency!",
+            min_screenings,
+            status
+        FROM film_order
+        WHERE order_id = $1
+        "#,
+        order_id.into_inner()
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match order {
+        Some(o) => Ok(HttpResponse::Ok().json(o)),
+        None => Err(AppError::NotFound("Film order not found".into())),
+    }
+}
+
+pub async fn create_film_order(
+    pool: web::Data<PgPool>,
+    new_order: web::Json<CreateFilmOrderRequest>,
+) -> Result<HttpResponse, AppError> {
+    let delivery_date = NaiveDate::parse_from_str(&new_order.delivery_date, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput("Dates must be in YYYY-MM-DD format".into()))?;
+
+    validate_amount("Rental cost", new_order.rental_cost)?;
+    if new_order.min_screenings.is_some_and(|m| m < 0) {
+        return Err(AppError::InvalidInput(
+            "Minimum screenings cannot be negative".into(),
+        ));
+    }
+    ensure_cinema_exists(pool.get_ref(), new_order.cinema_id).await?;
+    ensure_film_exists(pool.get_ref(), new_order.film_id).await?;
+    ensure_cinema_currency(pool.get_ref(), new_order.cinema_id, &new_order.currency).await?;
+
+    let employee_active = sqlx::query_scalar!(
+        "SELECT is_active FROM employee WHERE employee_id = $1",
+        new_order.employee_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match employee_active {
+        None => return Err(AppError::NotFound("Employee not found".into())),
+        Some(false) => {
+            return Err(AppError::InvalidInput(
+                "Employee is no longer active".into(),
+            ));
+        }
+        Some(true) => {}
+    }
+
+    let order = sqlx::query_as!(
+        FilmOrderResponse,
+        r#"
+        INSERT INTO film_order (
+            film_id, cinema_id, employee_id, delivery_date, rental_cost, min_screenings, status
+        )
+        VALUES ($1, $2, $3, $4, $5, COALESCE($6, 0), $7)
+        RETURNING
+            order_id,
+            film_id,
+            cinema_id,
+            employee_id,
+            delivery_date::text,
+            rental_cost,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = film_order.cinema_id
+            ) as "currency!",
+            min_screenings,
+            status
+        "#,
+        new_order.film_id,
+        new_order.cinema_id,
+        new_order.employee_id,
+        delivery_date,
+        new_order.rental_cost,
+        new_order.min_screenings,
+        STATUS_ORDERED
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Created().json(order))
+}
+
+// Переводит заказ в новый статус, если текущий статус входит в allowed_from
+async fn change_status(
+    pool: &PgPool,
+    order_id: i32,
+    allowed_from: &[&str],
+    new_status: &str,
+) -> Result<FilmOrderResponse, AppError> {
+    let allowed_from: Vec<String> = allowed_from.iter().map(|s| s.to_string()).collect();
+
+    let order = sqlx::query_as!(
+        FilmOrderResponse,
+        r#"
+        UPDATE film_order
+        SET status = $1
+        WHERE order_id = $2
+        AND status = ANY($3)
+        RETURNING
+            order_id,
+            film_id,
+            cinema_id,
+            employee_id,
+            delivery_date::text,
+            rental_cost,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = film_order.cinema_id
+            ) as "currency!",
+            min_screenings,
+            status
+        "#,
+        new_status,
+        order_id,
+        &allowed_from
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    if let Some(order) = order {
+        return Ok(order);
+    }
+
+    let current = sqlx::query_scalar!(
+        "SELECT status FROM film_order WHERE order_id = $1",
+        order_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match current {
+        Some(status) => Err(AppError::Conflict(format!(
+            "Film order is {} and cannot become {}",
+            status, new_status
+        ))),
+        None => Err(AppError::NotFound("Film order not found".into())),
+    }
+}
+
+pub async fn deliver_film_order(
+    pool: web::Data<PgPool>,
+    order_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let order = change_status(
+        pool.get_ref(),
+        order_id.into_inner(),
+        &[STATUS_ORDERED],
+        STATUS_DELIVERED,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(order))
+}
+
+pub async fn return_film_order(
+    pool: web::Data<PgPool>,
+    order_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let order = change_status(
+        pool.get_ref(),
+        order_id.into_inner(),
+        &[STATUS_DELIVERED],
+        STATUS_RETURNED,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(order))
+}
+
+pub async fn cancel_film_order(
+    pool: web::Data<PgPool>,
+    order_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let order = change_status(
+        pool.get_ref(),
+        order_id.into_inner(),
+        &[STATUS_ORDERED],
+        STATUS_CANCELLED,
+    )
+    .await?;
+
+    Ok(HttpResponse::Ok().json(order))
+}
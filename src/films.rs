@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 
 use crate::errors::AppError;
+use crate::sessions::ensure_film_sessions_fit;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FilmResponse {
@@ -13,6 +14,8 @@ pub struct FilmResponse {
     pub is_booking_available: bool,
     pub start_date: String,
     pub end_date: String,
+    pub duration_minutes: i32,
+    pub duration_estimated: bool, // хронометраж проставлен миграцией, а не указан
 }
 
 #[derive(Debug, Deserialize)]
@@ -22,6 +25,32 @@ pub struct CreateFilmRequest {
     pub is_booking_available: bool,
     pub start_date: String,
     pub end_date: String,
+    pub duration_minutes: i32,
+}
+
+fn validate_duration(duration_minutes: i32) -> Result<(), AppError> {
+    if duration_minutes <= 0 {
+        return Err(AppError::InvalidInput(
+            "Film duration must be positive".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+pub async fn ensure_film_exists(pool: &PgPool, film_id: i32) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM film WHERE film_id = $1) as "exists!""#,
+        film_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        return Err(AppError::NotFound("Film not found".into()));
+    }
+
+    Ok(())
 }
 
 pub async fn get_films(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
@@ -34,7 +63,9 @@ pub async fn get_films(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError
             age_restriction,
             is_booking_available,
             start_date::text,
-            end_date::text
+            end_date::text,
+            duration_minutes,
+            duration_estimated
         FROM film
         "#
     )
@@ -54,7 +85,9 @@ pub async fn get_active_films(pool: web::Data<PgPool>) -> Result<HttpResponse, A
             age_restriction,
             is_booking_available,
             start_date::text,
-            end_date::text
+            end_date::text,
+            duration_minutes,
+            duration_estimated
         FROM film
         WHERE end_date >= CURRENT_DATE
         "#
@@ -78,7 +111,9 @@ pub async fn get_film(
             age_restriction,
             is_booking_available,
             start_date::text,
-            end_date::text
+            end_date::text,
+            duration_minutes,
+            duration_estimated
         FROM film
         WHERE film_id = $1
         "#,
@@ -99,25 +134,31 @@ pub async fn create_film(
 ) -> Result<HttpResponse, AppError> {
     let start_date = NaiveDate::parse_from_str(&new_film.start_date, "%Y-%m-%d")?;
     let end_date = NaiveDate::parse_from_str(&new_film.end_date, "%Y-%m-%d")?;
+    validate_duration(new_film.duration_minutes)?;
 
     let film = sqlx::query_as!(
         FilmResponse,
         r#"
-        INSERT INTO film (title, age_restriction, is_booking_available, start_date, end_date)
-        VALUES ($1, $2, $3, $4, $5)
+        INSERT INTO film (
+            title, age_restriction, is_booking_available, start_date, end_date, duration_minutes
+        )
+        VALUES ($1, $2, $3, $4, $5, $6)
         RETURNING
             film_id,
             title,
             age_restriction,
             is_booking_available,
             start_date::text,
-            end_date::text
+            end_date::text,
+            duration_minutes,
+            duration_estimated
         "#,
         new_film.title,
         new_film.age_restriction,
         new_film.is_booking_available,
         start_date,
-        end_date
+        end_date,
+        new_film.duration_minutes
     )
     .fetch_one(pool.get_ref())
     .await?;
@@ -132,6 +173,18 @@ pub async fn update_film(
 ) -> Result<HttpResponse, AppError> {
     let start_date = NaiveDate::parse_from_str(&updated_film.start_date, "%Y-%m-%d")?;
     let end_date = NaiveDate::parse_from_str(&updated_film.end_date, "%Y-%m-%d")?;
+    validate_duration(updated_film.duration_minutes)?;
+    let film_id = film_id.into_inner();
+
+    let mut tx = pool.begin().await?;
+
+    let current_duration = sqlx::query_scalar!(
+        "SELECT duration_minutes FROM film WHERE film_id = $1 FOR UPDATE",
+        film_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Film not found".into()))?;
 
     let film = sqlx::query_as!(
         FilmResponse,
@@ -142,30 +195,40 @@ pub async fn update_film(
             age_restriction = $2,
             is_booking_available = $3,
             start_date = $4,
-            end_date = $5
-        WHERE film_id = $6
+            end_date = $5,
+            duration_minutes = $6,
+            duration_estimated = FALSE
+        WHERE film_id = $7
         RETURNING
             film_id,
             title,
             age_restriction,
             is_booking_available,
             start_date::text,
-            end_date::text
+            end_date::text,
+            duration_minutes,
+            duration_estimated
         "#,
         updated_film.title,
         updated_film.age_restriction,
         updated_film.is_booking_available,
         start_date,
         end_date,
-        film_id.into_inner()
+        updated_film.duration_minutes,
+        film_id
     )
-    .fetch_optional(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await?;
 
-    match film {
-        Some(f) => Ok(HttpResponse::Ok().json(f)),
-        None => Err(AppError::NotFound("Film not found".into())),
+    // Более длинный фильм может вылезти за закрытие или наехать
+    // на следующий сеанс в зале; такое изменение отклоняется целиком
+    if updated_film.duration_minutes > current_duration {
+        ensure_film_sessions_fit(&mut tx, film_id).await?;
     }
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(film))
 }
 
 pub async fn delete_film(
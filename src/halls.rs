@@ -0,0 +1,304 @@
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+
+pub const SCREEN_TYPES: [&str; 4] = ["standard", "3d", "imax", "4dx"];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HallResponse {
+    pub hall_id: i32,
+    pub cinema_id: i32,
+    pub name: String,
+    pub capacity: i32,
+    pub screen_type: String,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHallRequest {
+    pub name: String,
+    pub capacity: i32,
+    pub screen_type: String,
+    pub is_active: Option<bool>,
+}
+
+// Какие форматы сеансов может показывать зал с таким экраном.
+// 2D идёт в любом зале, 3D — везде, кроме обычного
+pub fn supports_format(screen_type: &str, format: &str) -> bool {
+    match format {
+        "2d" => true,
+        "3d" => screen_type != "standard",
+        "imax" => screen_type == "imax",
+        "4dx" => screen_type == "4dx",
+        _ => false,
+    }
+}
+
+fn validate_hall(hall: &CreateHallRequest) -> Result<(), AppError> {
+    if hall.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Hall name is required".into()));
+    }
+    if hall.capacity <= 0 {
+        return Err(AppError::InvalidInput(
+            "Hall capacity must be positive".into(),
+        ));
+    }
+    if !SCREEN_TYPES.contains(&hall.screen_type.as_str()) {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown screen type {}, expected one of {:?}",
+            hall.screen_type, SCREEN_TYPES
+        )));
+    }
+
+    Ok(())
+}
+
+pub async fn ensure_hall_exists(
+    pool: &PgPool,
+    cinema_id: i32,
+    hall_id: i32,
+) -> Result<(), AppError> {
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(SELECT 1 FROM hall WHERE hall_id = $1 AND cinema_id = $2) as "exists!"
+        "#,
+        hall_id,
+        cinema_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if !exists {
+        return Err(AppError::NotFound("Hall not found".into()));
+    }
+
+    Ok(())
+}
+
+// Зал, в котором можно ставить сеансы: принадлежит кинотеатру и не выведен из работы
+pub async fn find_active_hall<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+    hall_id: i32,
+) -> Result<HallResponse, AppError> {
+    let hall = sqlx::query_as!(
+        HallResponse,
+        r#"
+        SELECT
+            hall_id,
+            cinema_id,
+            name,
+            capacity,
+            screen_type,
+            is_active
+        FROM hall
+        WHERE hall_id = $1 AND cinema_id = $2
+        "#,
+        hall_id,
+        cinema_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Hall not found in this cinema".into()))?;
+
+    if !hall.is_active {
+        return Err(AppError::InvalidInput(format!(
+            "Hall {} is not active",
+            hall.name
+        )));
+    }
+
+    Ok(hall)
+}
+
+pub async fn get_halls(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let halls = sqlx::query_as!(
+        HallResponse,
+        r#"
+        SELECT
+            hall_id,
+            cinema_id,
+            name,
+            capacity,
+            screen_type,
+            is_active
+        FROM hall
+        WHERE cinema_id = $1
+        ORDER BY name
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(halls))
+}
+
+pub async fn get_hall(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+
+    let hall = sqlx::query_as!(
+        HallResponse,
+        r#"
+        SELECT
+            hall_id,
+            cinema_id,
+            name,
+            capacity,
+            screen_type,
+            is_active
+        FROM hall
+        WHERE cinema_id = $1 AND hall_id = $2
+        "#,
+        cinema_id,
+        hall_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match hall {
+        Some(h) => Ok(HttpResponse::Ok().json(h)),
+        None => Err(AppError::NotFound("Hall not found".into())),
+    }
+}
+
+pub async fn create_hall(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    new_hall: web::Json<CreateHallRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    validate_hall(&new_hall)?;
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let hall = sqlx::query_as!(
+        HallResponse,
+        r#"
+        INSERT INTO hall (cinema_id, name, capacity, screen_type, is_active)
+        VALUES ($1, $2, $3, $4, $5)
+        RETURNING
+            hall_id,
+            cinema_id,
+            name,
+            capacity,
+            screen_type,
+            is_active
+        "#,
+        cinema_id,
+        new_hall.name,
+        new_hall.capacity,
+        new_hall.screen_type,
+        new_hall.is_active.unwrap_or(true)
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Created().json(hall))
+}
+
+pub async fn update_hall(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    updated_hall: web::Json<CreateHallRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+    validate_hall(&updated_hall)?;
+
+    let seat_count = sqlx::query_scalar!(
+        r#"SELECT COUNT(*) as "count!" FROM seat WHERE hall_id = $1 AND is_active"#,
+        hall_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    if seat_count > 0 && seat_count != updated_hall.capacity as i64 {
+        return Err(AppError::InvalidInput(format!(
+            "Hall capacity is defined by its seat map ({} seats)",
+            seat_count
+        )));
+    }
+
+    let hall = sqlx::query_as!(
+        HallResponse,
+        r#"
+        UPDATE hall
+        SET
+            name = $1,
+            capacity = $2,
+            screen_type = $3,
+            is_active = $4
+        WHERE cinema_id = $5 AND hall_id = $6
+        RETURNING
+            hall_id,
+            cinema_id,
+            name,
+            capacity,
+            screen_type,
+            is_active
+        "#,
+        updated_hall.name,
+        updated_hall.capacity,
+        updated_hall.screen_type,
+        updated_hall.is_active.unwrap_or(true),
+        cinema_id,
+        hall_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    match hall {
+        Some(h) => Ok(HttpResponse::Ok().json(h)),
+        None => Err(AppError::NotFound("Hall not found".into())),
+    }
+}
+
+pub async fn delete_hall(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+
+    let has_sessions = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM session WHERE cinema_id = $1 AND hall_id = $2
+        ) as "exists!"
+        "#,
+        cinema_id,
+        hall_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    if has_sessions {
+        return Err(AppError::Conflict(
+            "Hall has sessions, deactivate it instead".into(),
+        ));
+    }
+
+    let result = sqlx::query!(
+        "DELETE FROM hall WHERE cinema_id = $1 AND hall_id = $2",
+        cinema_id,
+        hall_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Hall not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -26,14 +26,12 @@ pub async fn create_cinema(
     let closing_time = NaiveTime::parse_from_str(&new_cinema.closing_time, "%H:%M:%S");
 
     let cinema = sqlx::query_as::<_, Cinema>(
-        "INSERT INTO cinema (name, address, employee_count, hall_count, opening_time, closing_time)
-         VALUES ($1, $2, $3, $4, $5, $6)
+        "INSERT INTO cinema (name, address, opening_time, closing_time)
+         VALUES ($1, $2, $3, $4)
          RETURNING *",
     )
     .bind(&new_cinema.name)
     .bind(&new_cinema.address)
-    .bind(new_cinema.employee_count)
-    .bind(new_cinema.hall_count)
     .bind(opening_time.unwrap())
     .bind(closing_time.unwrap())
     .fetch_one(pool.get_ref())
@@ -0,0 +1,322 @@
+use std::time::Duration;
+
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::availability::ensure_capacity;
+use crate::errors::AppError;
+use crate::seats::{
+    SeatHolder, WithSeats, assign_seats, fetch_assigned_seats, resolve_ticket_count,
+};
+
+pub const DEFAULT_HOLD_MINUTES: i32 = 10;
+pub const MAX_HOLD_MINUTES: i32 = 30;
+// Сколько всего может жить удержание с учётом продлений, считая от создания
+pub const MAX_HOLD_LIFETIME_MINUTES: i32 = 60;
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoldResponse {
+    pub hold_id: i32,
+    pub session_id: i32,
+    pub customer_id: Option<i32>,
+    pub ticket_count: i32,
+    pub created_at: String,
+    pub expires_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateHoldRequest {
+    pub session_id: i32,
+    pub customer_id: Option<i32>,
+    pub ticket_count: Option<i32>,
+    pub seat_ids: Option<Vec<i32>>,
+    pub minutes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExtendHoldRequest {
+    pub minutes: Option<i32>,
+}
+
+fn hold_minutes(minutes: Option<i32>) -> Result<i32, AppError> {
+    let minutes = minutes.unwrap_or(DEFAULT_HOLD_MINUTES);
+    if minutes <= 0 || minutes > MAX_HOLD_MINUTES {
+        return Err(AppError::InvalidInput(format!(
+            "Hold duration must be between 1 and {} minutes",
+            MAX_HOLD_MINUTES
+        )));
+    }
+
+    Ok(minutes)
+}
+
+// Что было удержано: заказ по удержанию получает ровно эти места
+#[derive(Debug)]
+pub struct HeldSeats {
+    pub ticket_count: i32,
+    pub seat_ids: Vec<i32>,
+}
+
+impl HeldSeats {
+    // Подставляет удержанное в запрос, где места или количество не указаны,
+    // и отклоняет запрос, если указано не то, что было удержано
+    pub fn fill_request(
+        &self,
+        seat_ids: &mut Vec<i32>,
+        ticket_count: &mut Option<i32>,
+    ) -> Result<(), AppError> {
+        if seat_ids.is_empty() {
+            seat_ids.clone_from(&self.seat_ids);
+        } else {
+            let mut requested = seat_ids.clone();
+            requested.sort_unstable();
+            if requested != self.seat_ids {
+                return Err(AppError::InvalidInput(format!(
+                    "Seats do not match the hold, held seats are {:?}",
+                    self.seat_ids
+                )));
+            }
+        }
+
+        match *ticket_count {
+            Some(count) if count != self.ticket_count => Err(AppError::InvalidInput(format!(
+                "Hold is for {} tickets, got {}",
+                self.ticket_count, count
+            ))),
+            _ => {
+                *ticket_count = Some(self.ticket_count);
+                Ok(())
+            }
+        }
+    }
+}
+
+// Снимает удержание, по которому оформляется бронь или продажа,
+// чтобы его места можно было закрепить за новой записью в той же транзакции.
+// Удержание клиента может использовать только он сам, удержание без клиента — любой.
+// Возвращает удержанные места и количество
+pub async fn consume_hold(
+    conn: &mut PgConnection,
+    hold_id: i32,
+    session_id: i32,
+    customer_id: i32,
+) -> Result<HeldSeats, AppError> {
+    let hold = sqlx::query!(
+        r#"
+        SELECT ticket_count, customer_id
+        FROM seat_hold
+        WHERE hold_id = $1 AND session_id = $2 AND expires_at > NOW()
+        FOR UPDATE
+        "#,
+        hold_id,
+        session_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::Conflict("Hold has expired or belongs to another session".into()))?;
+
+    if hold.customer_id.is_some_and(|owner| owner != customer_id) {
+        return Err(AppError::Conflict(
+            "Hold belongs to another customer".into(),
+        ));
+    }
+
+    let seat_ids = sqlx::query_scalar!(
+        "SELECT seat_id FROM session_seat WHERE hold_id = $1 ORDER BY seat_id",
+        hold_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM seat_hold WHERE hold_id = $1", hold_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(HeldSeats {
+        ticket_count: hold.ticket_count,
+        seat_ids,
+    })
+}
+
+// Фоновая задача: периодически удаляет истёкшие удержания,
+// места освобождаются каскадно через session_seat
+pub async fn expire_holds(pool: PgPool) {
+    let mut interval = actix_web::rt::time::interval(EXPIRY_SWEEP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match sqlx::query!("DELETE FROM seat_hold WHERE expires_at <= NOW()")
+            .execute(&pool)
+            .await
+        {
+            Ok(result) if result.rows_affected() > 0 => {
+                log::info!("Released {} expired seat holds", result.rows_affected());
+            }
+            Ok(_) => {}
+            Err(err) => log::error!("Failed to release expired seat holds: {}", err),
+        }
+    }
+}
+
+pub async fn get_hold(
+    pool: web::Data<PgPool>,
+    hold_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let hold_id = hold_id.into_inner();
+
+    let hold = sqlx::query_as!(
+        HoldResponse,
+        r#"
+        SELECT
+            hold_id,
+            session_id,
+            customer_id,
+            ticket_count,
+            to_local_iso(created_at, session_timezone(session_id)) as "created_at!",
+            to_local_iso(expires_at, session_timezone(session_id)) as "expires_at!"
+        FROM seat_hold
+        WHERE hold_id = $1 AND expires_at > NOW()
+        "#,
+        hold_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Hold not found or expired".into()))?;
+
+    let seats = fetch_assigned_seats(pool.get_ref(), SeatHolder::Hold(hold_id)).await?;
+
+    Ok(HttpResponse::Ok().json(WithSeats { inner: hold, seats }))
+}
+
+pub async fn create_hold(
+    pool: web::Data<PgPool>,
+    new_hold: web::Json<CreateHoldRequest>,
+) -> Result<HttpResponse, AppError> {
+    let minutes = hold_minutes(new_hold.minutes)?;
+    let seat_ids = new_hold.seat_ids.clone().unwrap_or_default();
+
+    let mut tx = pool.begin().await?;
+
+    let ticket_count = resolve_ticket_count(
+        &mut tx,
+        new_hold.session_id,
+        new_hold.ticket_count,
+        &seat_ids,
+    )
+    .await?;
+
+    ensure_capacity(&mut tx, new_hold.session_id, ticket_count).await?;
+
+    let hold = sqlx::query_as!(
+        HoldResponse,
+        r#"
+        INSERT INTO seat_hold (session_id, customer_id, ticket_count, expires_at)
+        VALUES ($1, $2, $3, NOW() + make_interval(mins => $4))
+        RETURNING
+            hold_id,
+            session_id,
+            customer_id,
+            ticket_count,
+            to_local_iso(created_at, session_timezone(session_id)) as "created_at!",
+            to_local_iso(expires_at, session_timezone(session_id)) as "expires_at!"
+        "#,
+        new_hold.session_id,
+        new_hold.customer_id,
+        ticket_count,
+        minutes
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let seats = assign_seats(
+        &mut tx,
+        new_hold.session_id,
+        &seat_ids,
+        SeatHolder::Hold(hold.hold_id),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(WithSeats { inner: hold, seats }))
+}
+
+// Продление отсчитывается от текущего момента, но удержание не может
+// жить дольше MAX_HOLD_LIFETIME_MINUTES от создания, иначе места
+// можно было бы держать бесконечно
+pub async fn extend_hold(
+    pool: web::Data<PgPool>,
+    hold_id: web::Path<i32>,
+    extension: web::Json<ExtendHoldRequest>,
+) -> Result<HttpResponse, AppError> {
+    let hold_id = hold_id.into_inner();
+    let minutes = hold_minutes(extension.minutes)?;
+
+    let hold = sqlx::query_as!(
+        HoldResponse,
+        r#"
+        UPDATE seat_hold
+        SET expires_at = NOW() + make_interval(mins => $1)
+        WHERE hold_id = $2
+        AND expires_at > NOW()
+        AND NOW() + make_interval(mins => $1) <= created_at + make_interval(mins => $3)
+        RETURNING
+            hold_id,
+            session_id,
+            customer_id,
+            ticket_count,
+            to_local_iso(created_at, session_timezone(session_id)) as "created_at!",
+            to_local_iso(expires_at, session_timezone(session_id)) as "expires_at!"
+        "#,
+        minutes,
+        hold_id,
+        MAX_HOLD_LIFETIME_MINUTES
+    )
+    .fetch_optional(pool.get_ref())
+    .await?;
+
+    if let Some(hold) = hold {
+        return Ok(HttpResponse::Ok().json(hold));
+    }
+
+    let is_active = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM seat_hold WHERE hold_id = $1 AND expires_at > NOW()
+        ) as "exists!"
+        "#,
+        hold_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    if is_active {
+        return Err(AppError::Conflict(format!(
+            "Hold cannot be kept longer than {} minutes after it was created",
+            MAX_HOLD_LIFETIME_MINUTES
+        )));
+    }
+
+    Err(AppError::NotFound("Hold not found or expired".into()))
+}
+
+pub async fn release_hold(
+    pool: web::Data<PgPool>,
+    hold_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM seat_hold WHERE hold_id = $1",
+        hold_id.into_inner()
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Hold not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
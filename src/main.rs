@@ -1,8 +1,22 @@
+mod availability;
 mod cinemas;
 mod films;
+mod film_orders;
+mod halls;
+mod holds;
+mod money;
+mod opening_hours;
+mod pricing;
+mod schedule_proposals;
+mod schedule_templates;
+mod seats;
 mod sessions;
+mod surcharges;
+mod ticket_types;
 mod tickets;
 mod bookings;
+mod customers;
+mod employees;
 mod models;
 mod handlers;
 mod db;
@@ -20,6 +34,8 @@ async fn main() -> std::io::Result<()> {
     let pool = init_db_pool().await
         .expect("Failed to create database connection pool");
 
+    actix_web::rt::spawn(holds::expire_holds(pool.clone()));
+
     HttpServer::new(move || {
         App::new()
             .app_data(actix_web::web::Data::new(pool.clone()))
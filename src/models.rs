@@ -1,28 +1,58 @@
 use serde::{Deserialize, Serialize};
-use chrono::{NaiveDate, NaiveTime, NaiveDateTime};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use sqlx::FromRow;
+use rust_decimal::Decimal;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Cinema {
     pub cinema_id: i32,
     pub name: String,
     pub address: String,
-    pub employee_count: i32,
-    pub hall_count: i32,
     pub opening_time: NaiveTime,
     pub closing_time: NaiveTime,
+    pub ad_minutes: i32,
+    pub cleaning_minutes: i32,
+    pub timezone: String,  // Например "Europe/Moscow"
+    pub currency: String,  // ISO 4217, например "RUB"
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewCinema {
     pub name: String,
     pub address: String,
-    pub employee_count: i32,
-    pub hall_count: i32,
     pub opening_time: String,  // Формат "HH:MM:SS"
     pub closing_time: String,  // Формат "HH:MM:SS"
 }
 
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Hall {
+    pub hall_id: i32,
+    pub cinema_id: i32,
+    pub name: String,
+    pub capacity: i32,
+    pub screen_type: String,  // "standard", "3d", "imax", "4dx"
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NewHall {
+    pub cinema_id: i32,
+    pub name: String,
+    pub capacity: i32,
+    pub screen_type: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Seat {
+    pub seat_id: i32,
+    pub hall_id: i32,
+    pub row_number: i32,
+    pub seat_number: i32,
+    pub column_index: i32,
+    pub category: String,  // "standard", "vip", "sofa", "wheelchair"
+    pub is_active: bool,  // FALSE — место из прежней схемы зала
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Film {
     pub film_id: i32,
@@ -31,6 +61,8 @@ pub struct Film {
     pub is_booking_available: bool,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
+    pub duration_minutes: i32,
+    pub duration_estimated: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +72,7 @@ pub struct NewFilm {
     pub is_booking_available: bool,
     pub start_date: String,  // Формат "YYYY-MM-DD"
     pub end_date: String,    // Формат "YYYY-MM-DD"
+    pub duration_minutes: i32,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -65,6 +98,7 @@ pub struct Employee {
     pub last_name: String,
     pub position: String,
     pub age: i32,
+    pub is_active: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -81,16 +115,50 @@ pub struct Session {
     pub session_id: i32,
     pub film_id: i32,
     pub cinema_id: i32,
-    pub start_time: NaiveDateTime,
-    pub ticket_price: f64,
+    pub hall_id: Option<i32>,
+    pub start_time: DateTime<Utc>,
+    pub pricing_mode: String,  // "fixed", "dynamic"
+    pub ticket_price: Decimal,
+    pub format: String,  // "2d", "3d", "imax", "4dx"
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+    pub status: String,  // "scheduled", "cancelled"
+    pub cancelled_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NewSession {
     pub film_id: i32,
     pub cinema_id: i32,
-    pub start_time: String,  // Формат "YYYY-MM-DD HH:MM:SS"
-    pub ticket_price: f64,
+    pub hall_id: Option<i32>,
+    pub start_time: String,  // Формат "YYYY-MM-DD HH:MM:SS", местное время кинотеатра
+    pub ticket_price: Option<Decimal>,  // None — цену дают правила кинотеатра
+    pub currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ScheduleTemplate {
+    pub template_id: i32,
+    pub cinema_id: i32,
+    pub film_id: i32,
+    pub hall_id: Option<i32>,
+    pub ticket_price: Decimal,
+    pub weekdays: Vec<i32>,  // 1 — понедельник, 7 — воскресенье
+    pub times: Vec<NaiveTime>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub format: String,
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct FormatSurcharge {
+    pub cinema_id: i32,
+    pub format: String,
+    pub amount: Decimal,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -100,7 +168,69 @@ pub struct TicketSale {
     pub customer_id: i32,
     pub employee_id: i32,
     pub ticket_count: i32,
-    pub sale_time: NaiveDateTime,
+    pub sale_time: DateTime<Utc>,
+    pub unit_price: Decimal,
+    pub discount: Decimal,
+    pub total_price: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TicketType {
+    pub ticket_type_id: i32,
+    pub cinema_id: i32,
+    pub code: String,  // "adult", "child", "student", "senior" или свой
+    pub name: String,
+    pub percent: Decimal,  // процент от базовой цены сеанса
+    pub adjustment: Decimal,
+    pub is_child: bool,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PriceRule {
+    pub rule_id: i32,
+    pub cinema_id: i32,
+    pub name: String,
+    pub priority: i32,  // применяются по возрастанию
+    pub valid_from: Option<NaiveDate>,
+    pub valid_to: Option<NaiveDate>,
+    pub weekdays: Option<Vec<i32>>,  // 1 — понедельник, 7 — воскресенье
+    pub time_from: Option<NaiveTime>,
+    pub time_to: Option<NaiveTime>,
+    pub premiere_weeks: Option<i32>,
+    pub format: Option<String>,
+    pub seat_category: Option<String>,
+    pub min_occupancy: Option<Decimal>,  // процент занятых мест
+    pub action: String,  // "set", "percent", "add"
+    pub value: Decimal,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct TicketSaleItem {
+    pub item_id: i32,
+    pub sale_id: i32,
+    pub ticket_type_id: i32,
+    pub seat_category: Option<String>,
+    pub quantity: i32,
+    pub unit_price: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct BookingItem {
+    pub booking_id: i32,
+    pub ticket_type_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Refund {
+    pub refund_id: i32,
+    pub sale_id: i32,
+    pub amount: Decimal,
+    pub reason: String,  // "session_cancelled"
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -118,7 +248,9 @@ pub struct FilmOrder {
     pub cinema_id: i32,
     pub employee_id: i32,
     pub delivery_date: NaiveDate,
-    pub rental_cost: f64,
+    pub rental_cost: Decimal,
+    pub min_screenings: i32,
+    pub status: String,  // "ordered", "delivered", "returned", "cancelled"
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -127,7 +259,9 @@ pub struct NewFilmOrder {
     pub cinema_id: i32,
     pub employee_id: i32,
     pub delivery_date: String,  // Формат "YYYY-MM-DD"
-    pub rental_cost: f64,
+    pub rental_cost: Decimal,
+    pub currency: String,
+    pub min_screenings: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -136,7 +270,7 @@ pub struct Booking {
     pub session_id: i32,
     pub customer_id: i32,
     pub ticket_count: i32,
-    pub booking_time: NaiveDateTime,
+    pub booking_time: DateTime<Utc>,
     pub status: String,  // "active", "completed", "cancelled"
 }
 
@@ -145,4 +279,23 @@ pub struct NewBooking {
     pub session_id: i32,
     pub customer_id: i32,
     pub ticket_count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SessionSeat {
+    pub session_id: i32,
+    pub seat_id: i32,
+    pub booking_id: Option<i32>,
+    pub sale_id: Option<i32>,
+    pub hold_id: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct SeatHold {
+    pub hold_id: i32,
+    pub session_id: i32,
+    pub customer_id: Option<i32>,
+    pub ticket_count: i32,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
 }
\ No newline at end of file
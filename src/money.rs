@@ -0,0 +1,79 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::PgExecutor;
+
+use crate::errors::AppError;
+
+// Суммы — rust_decimal::Decimal поверх NUMERIC(12, 2), в JSON передаются
+// строками ("350.00"), чтобы не терять копейки на float.
+// Валюта у всех сумм кинотеатра одна — cinema.currency
+pub const DEFAULT_CURRENCY: &str = "RUB";
+pub const MONEY_SCALE: u32 = 2;
+
+pub fn validate_currency_code(currency: &str) -> Result<(), AppError> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(AppError::InvalidInput(format!(
+            "Invalid currency {}, expected an ISO 4217 code like \"RUB\"",
+            currency
+        )));
+    }
+
+    Ok(())
+}
+
+// Неотрицательная сумма не точнее копейки
+pub fn validate_amount(field: &str, amount: Decimal) -> Result<(), AppError> {
+    if amount < Decimal::ZERO {
+        return Err(AppError::InvalidInput(format!(
+            "{} cannot be negative",
+            field
+        )));
+    }
+    if amount.normalize().scale() > MONEY_SCALE {
+        return Err(AppError::InvalidInput(format!(
+            "{} must have at most {} decimal places",
+            field, MONEY_SCALE
+        )));
+    }
+
+    Ok(())
+}
+
+// Округление до копеек по кассовым правилам: половина — вверх
+pub fn round_money(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(MONEY_SCALE, RoundingStrategy::MidpointAwayFromZero)
+}
+
+pub async fn cinema_currency<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+) -> Result<String, AppError> {
+    sqlx::query_scalar!(
+        r#"SELECT currency::text as "currency!" FROM cinema WHERE cinema_id = $1"#,
+        cinema_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Cinema not found".into()))
+}
+
+// Сумма в запросе должна быть в валюте кинотеатра: пересчёта курсов нет
+pub async fn ensure_cinema_currency<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+    currency: &str,
+) -> Result<(), AppError> {
+    let expected = cinema_currency(executor, cinema_id).await?;
+    ensure_currency(&expected, currency)
+}
+
+// То же, когда валюта кинотеатра уже прочитана вместе с сеансом
+pub fn ensure_currency(expected: &str, currency: &str) -> Result<(), AppError> {
+    if expected != currency {
+        return Err(AppError::InvalidInput(format!(
+            "Amounts for this cinema must be in {}, got {}",
+            expected, currency
+        )));
+    }
+
+    Ok(())
+}
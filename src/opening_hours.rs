@@ -0,0 +1,399 @@
+use actix_web::{HttpResponse, web};
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgExecutor, PgPool};
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+
+const MAX_HOURS_RANGE_DAYS: i64 = 366;
+
+// Часы работы на конкретную дату. Приоритет источников:
+// исключение на дату ("exception"), затем недельное расписание ("weekly"),
+// затем opening_time/closing_time самого кинотеатра ("default")
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayHoursResponse {
+    pub date: NaiveDate,
+    pub weekday: i32, // 1 — понедельник, 7 — воскресенье
+    pub is_closed: bool,
+    pub opening_time: Option<NaiveTime>,
+    pub closing_time: Option<NaiveTime>,
+    pub source: String,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WeeklyHoursResponse {
+    pub weekday: i32,
+    pub is_closed: bool,
+    pub opening_time: String,
+    pub closing_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeeklyHoursDayRequest {
+    pub weekday: i32,
+    pub is_closed: bool,
+    pub opening_time: String,
+    pub closing_time: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeeklyHoursRequest {
+    pub days: Vec<WeeklyHoursDayRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HoursExceptionResponse {
+    pub cinema_id: i32,
+    pub date: String,
+    pub is_closed: bool,
+    pub opening_time: Option<String>,
+    pub closing_time: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoursExceptionRequest {
+    pub date: String, // Формат "YYYY-MM-DD"
+    pub is_closed: bool,
+    pub opening_time: Option<String>,
+    pub closing_time: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct HoursRangeQuery {
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+// Интервал работы рабочего дня. Если закрытие не позже открытия,
+// кинотеатр закрывается уже на следующие сутки
+pub fn opening_window(day: &DayHoursResponse) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    if day.is_closed {
+        return None;
+    }
+
+    let (opening_time, closing_time) = (day.opening_time?, day.closing_time?);
+    let opens = day.date.and_time(opening_time);
+    let closes = if closing_time > opening_time {
+        day.date.and_time(closing_time)
+    } else {
+        day.date.and_time(closing_time) + Duration::days(1)
+    };
+
+    Some((opens, closes))
+}
+
+pub async fn resolve_hours<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Result<Vec<DayHoursResponse>, AppError> {
+    let days = sqlx::query_as!(
+        DayHoursResponse,
+        r#"
+        SELECT
+            d::date as "date!",
+            EXTRACT(ISODOW FROM d)::int as "weekday!",
+            COALESCE(e.is_closed, w.is_closed, FALSE) as "is_closed!",
+            CASE
+                WHEN e.cinema_id IS NOT NULL THEN e.opening_time
+                WHEN w.cinema_id IS NOT NULL THEN w.opening_time
+                ELSE c.opening_time
+            END as "opening_time?",
+            CASE
+                WHEN e.cinema_id IS NOT NULL THEN e.closing_time
+                WHEN w.cinema_id IS NOT NULL THEN w.closing_time
+                ELSE c.closing_time
+            END as "closing_time?",
+            CASE
+                WHEN e.cinema_id IS NOT NULL THEN 'exception'
+                WHEN w.cinema_id IS NOT NULL THEN 'weekly'
+                ELSE 'default'
+            END as "source!",
+            e.note as "note?"
+        FROM cinema c
+        CROSS JOIN generate_series($2::date, $3::date, interval '1 day') d
+        LEFT JOIN cinema_hours w
+            ON w.cinema_id = c.cinema_id AND w.weekday = EXTRACT(ISODOW FROM d)
+        LEFT JOIN cinema_hours_exception e
+            ON e.cinema_id = c.cinema_id AND e.date = d::date
+        WHERE c.cinema_id = $1
+        ORDER BY d
+        "#,
+        cinema_id,
+        from,
+        to
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(days)
+}
+
+// Сеанс должен целиком попасть в часы работы одного рабочего дня:
+// того, в который он начался, или предыдущего, если тот закрывается после полуночи
+pub async fn ensure_within_opening_hours<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+    start_time: NaiveDateTime,
+    end_time: NaiveDateTime,
+) -> Result<(), AppError> {
+    let day = start_time.date();
+    let from = day.pred_opt().unwrap_or(day);
+    let days = resolve_hours(executor, cinema_id, from, day).await?;
+
+    let fits = days
+        .iter()
+        .filter_map(opening_window)
+        .any(|(opens, closes)| opens <= start_time && end_time <= closes);
+
+    if !fits {
+        return Err(AppError::InvalidInput(format!(
+            "Session {} - {} is outside cinema opening hours",
+            start_time, end_time
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid time {}, expected HH:MM:SS", value)))
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", value)))
+}
+
+// Сегодняшняя дата в часовом поясе кинотеатра, а не сервера
+async fn cinema_today(pool: &PgPool, cinema_id: i32) -> Result<NaiveDate, AppError> {
+    let today = sqlx::query_scalar!(
+        r#"SELECT (NOW() AT TIME ZONE timezone)::date as "today!" FROM cinema WHERE cinema_id = $1"#,
+        cinema_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Cinema not found".into()))?;
+
+    Ok(today)
+}
+
+pub async fn get_cinema_hours(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    range: web::Query<HoursRangeQuery>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    let today = cinema_today(pool.get_ref(), cinema_id).await?;
+
+    let from = match &range.from {
+        Some(from) => parse_date(from)?,
+        None => today,
+    };
+    let to = match &range.to {
+        Some(to) => parse_date(to)?,
+        None => from + Duration::days(6),
+    };
+
+    if to < from {
+        return Err(AppError::InvalidInput(
+            "'to' must not be before 'from'".into(),
+        ));
+    }
+    if (to - from).num_days() > MAX_HOURS_RANGE_DAYS {
+        return Err(AppError::InvalidInput(format!(
+            "Range cannot exceed {} days",
+            MAX_HOURS_RANGE_DAYS
+        )));
+    }
+
+    let days = resolve_hours(pool.get_ref(), cinema_id, from, to).await?;
+
+    Ok(HttpResponse::Ok().json(days))
+}
+
+async fn fetch_weekly_hours(
+    pool: &PgPool,
+    cinema_id: i32,
+) -> Result<Vec<WeeklyHoursResponse>, AppError> {
+    let days = sqlx::query_as!(
+        WeeklyHoursResponse,
+        r#"
+        SELECT
+            weekday::int as "weekday!",
+            is_closed,
+            opening_time::text as "opening_time!",
+            closing_time::text as "closing_time!"
+        FROM cinema_hours
+        WHERE cinema_id = $1
+        ORDER BY weekday
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(days)
+}
+
+pub async fn get_weekly_hours(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let days = fetch_weekly_hours(pool.get_ref(), cinema_id).await?;
+
+    Ok(HttpResponse::Ok().json(days))
+}
+
+// Полностью заменяет недельное расписание; дни, которых нет в запросе,
+// работают по opening_time/closing_time кинотеатра
+pub async fn update_weekly_hours(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    weekly: web::Json<WeeklyHoursRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let mut weekdays = Vec::new();
+    let mut closed = Vec::new();
+    let mut opening_times = Vec::new();
+    let mut closing_times = Vec::new();
+
+    for day in &weekly.days {
+        if !(1..=7).contains(&day.weekday) {
+            return Err(AppError::InvalidInput(
+                "Weekday must be between 1 (Monday) and 7 (Sunday)".into(),
+            ));
+        }
+        if weekdays.contains(&day.weekday) {
+            return Err(AppError::InvalidInput(format!(
+                "Weekday {} is listed twice",
+                day.weekday
+            )));
+        }
+        weekdays.push(day.weekday);
+        closed.push(day.is_closed);
+        opening_times.push(parse_time(&day.opening_time)?);
+        closing_times.push(parse_time(&day.closing_time)?);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!("DELETE FROM cinema_hours WHERE cinema_id = $1", cinema_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO cinema_hours (cinema_id, weekday, is_closed, opening_time, closing_time)
+        SELECT $1, * FROM UNNEST($2::int[], $3::bool[], $4::time[], $5::time[])
+        "#,
+        cinema_id,
+        &weekdays,
+        &closed,
+        &opening_times,
+        &closing_times
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let days = fetch_weekly_hours(pool.get_ref(), cinema_id).await?;
+
+    Ok(HttpResponse::Ok().json(days))
+}
+
+// Создаёт или заменяет исключение на дату: выходной или особые часы работы
+pub async fn upsert_hours_exception(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    exception: web::Json<HoursExceptionRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let date = parse_date(&exception.date)?;
+    let opening_time = exception
+        .opening_time
+        .as_deref()
+        .map(parse_time)
+        .transpose()?;
+    let closing_time = exception
+        .closing_time
+        .as_deref()
+        .map(parse_time)
+        .transpose()?;
+
+    if !exception.is_closed && (opening_time.is_none() || closing_time.is_none()) {
+        return Err(AppError::InvalidInput(
+            "Opening and closing time are required unless the cinema is closed".into(),
+        ));
+    }
+
+    let saved = sqlx::query_as!(
+        HoursExceptionResponse,
+        r#"
+        INSERT INTO cinema_hours_exception
+            (cinema_id, date, is_closed, opening_time, closing_time, note)
+        VALUES ($1, $2, $3, $4, $5, $6)
+        ON CONFLICT (cinema_id, date) DO UPDATE
+        SET
+            is_closed = EXCLUDED.is_closed,
+            opening_time = EXCLUDED.opening_time,
+            closing_time = EXCLUDED.closing_time,
+            note = EXCLUDED.note
+        RETURNING
+            cinema_id,
+            date::text as "date!",
+            is_closed,
+            opening_time::text,
+            closing_time::text,
+            note
+        "#,
+        cinema_id,
+        date,
+        exception.is_closed,
+        opening_time,
+        closing_time,
+        exception.note
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(saved))
+}
+
+pub async fn delete_hours_exception(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, String)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, date) = path.into_inner();
+    let date = parse_date(&date)?;
+
+    let result = sqlx::query!(
+        "DELETE FROM cinema_hours_exception WHERE cinema_id = $1 AND date = $2",
+        cinema_id,
+        date
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound(
+            "Opening hours exception not found".into(),
+        ));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
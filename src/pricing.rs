@@ -0,0 +1,1023 @@
+use actix_web::{HttpResponse, web};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+use crate::availability::{Occupancy, fetch_occupancy};
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+use crate::money::{ensure_cinema_currency, round_money, validate_amount};
+use crate::seats::SEAT_CATEGORIES;
+use crate::sessions::{SESSION_FORMATS, STATUS_SCHEDULED};
+use crate::ticket_types::{MAX_PERCENT, ticket_type_price};
+
+// Сеанс с ценой, введённой вручную, и сеанс, цену которого дают правила
+pub const PRICING_FIXED: &str = "fixed";
+pub const PRICING_DYNAMIC: &str = "dynamic";
+
+const ACTION_SET: &str = "set"; // цена становится равной value
+const ACTION_PERCENT: &str = "percent"; // цена умножается на value / 100
+const ACTION_ADD: &str = "add"; // к цене прибавляется value, со знаком минус — скидка
+const RULE_ACTIONS: [&str; 3] = [ACTION_SET, ACTION_PERCENT, ACTION_ADD];
+
+// Правило цены кинотеатра. Пустое условие подходит под любой сеанс.
+// Даты, дни недели и время — местные дата и время начала сеанса
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceRuleResponse {
+    pub rule_id: i32,
+    pub cinema_id: i32,
+    pub name: String,
+    pub priority: i32, // правила применяются по возрастанию priority
+    pub valid_from: Option<NaiveDate>,
+    pub valid_to: Option<NaiveDate>,
+    pub weekdays: Option<Vec<i32>>, // 1 — понедельник, 7 — воскресенье
+    pub time_from: Option<NaiveTime>, // time_from > time_to — интервал через полночь
+    pub time_to: Option<NaiveTime>,
+    pub premiere_weeks: Option<i32>, // первые недели проката фильма
+    pub format: Option<String>,
+    pub seat_category: Option<String>,
+    pub min_occupancy: Option<Decimal>, // процент занятых мест, с которого действует правило
+    pub action: String,                 // "set", "percent", "add"
+    pub value: Decimal,
+    pub currency: String,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePriceRuleRequest {
+    pub name: String,
+    pub priority: Option<i32>,
+    pub valid_from: Option<String>, // Формат "YYYY-MM-DD"
+    pub valid_to: Option<String>,   // Формат "YYYY-MM-DD"
+    pub weekdays: Option<Vec<i32>>,
+    pub time_from: Option<String>, // Формат "HH:MM:SS"
+    pub time_to: Option<String>,   // Формат "HH:MM:SS"
+    pub premiere_weeks: Option<i32>,
+    pub format: Option<String>,
+    pub seat_category: Option<String>,
+    pub min_occupancy: Option<Decimal>,
+    pub action: String,
+    pub value: Decimal,
+    pub currency: String, // валюта value, должна совпадать с валютой кинотеатра
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PriceQuery {
+    pub seat_category: Option<String>, // None — свободная рассадка или цена для списков
+    pub ticket_type_id: Option<i32>,
+}
+
+// Шаг расчёта цены: откуда взялось изменение и какой стала цена после него
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceStep {
+    pub source: String, // "ticket_price", "rule", "surcharge", "ticket_type"
+    pub rule_id: Option<i32>,
+    pub name: String,
+    pub action: String, // "set", "percent", "add"
+    pub value: Decimal,
+    pub price: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PriceExplanation {
+    pub session_id: i32,
+    pub pricing_mode: String,
+    pub seat_category: Option<String>,
+    pub ticket_type_id: Option<i32>,
+    pub occupancy: Decimal, // процент занятых мест на момент расчёта
+    pub steps: Vec<PriceStep>,
+    pub price: Decimal,
+    pub currency: String,
+}
+
+// Всё, от чего зависят условия правил
+#[derive(Debug)]
+struct PricingContext {
+    start_time: NaiveDateTime, // местное время кинотеатра
+    film_start_date: NaiveDate,
+    format: String,
+    seat_category: Option<String>,
+    occupancy: Decimal,
+}
+
+struct SessionPricing {
+    cinema_id: i32,
+    pricing_mode: String,
+    ticket_price: Decimal,
+    surcharge: Decimal,
+    currency: String,
+    start_time: NaiveDateTime,
+    film_start_date: NaiveDate,
+    format: String,
+}
+
+impl SessionPricing {
+    fn context(&self, seat_category: Option<String>, occupancy: Decimal) -> PricingContext {
+        PricingContext {
+            start_time: self.start_time,
+            film_start_date: self.film_start_date,
+            format: self.format.clone(),
+            seat_category,
+            occupancy,
+        }
+    }
+}
+
+// Базовые цены продажи с доплатой за формат. У фиксированного сеанса
+// цена одна, у динамического — своя для каждой категории мест
+pub struct SalePricing {
+    pub currency: String,
+    pub list_price: Decimal, // цена места без категории: свободная рассадка
+    seat_prices: Vec<(Option<String>, Decimal)>,
+}
+
+impl SalePricing {
+    pub fn base_price(&self, seat_category: Option<&str>) -> Decimal {
+        self.seat_prices
+            .iter()
+            .find(|(category, _)| category.as_deref() == seat_category)
+            .map(|(_, price)| *price)
+            .unwrap_or(self.list_price)
+    }
+}
+
+fn in_time_band(time: NaiveTime, from: Option<NaiveTime>, to: Option<NaiveTime>) -> bool {
+    match (from, to) {
+        (Some(from), Some(to)) if from <= to => from <= time && time < to,
+        (Some(from), Some(to)) => time >= from || time < to,
+        _ => true,
+    }
+}
+
+impl PriceRuleResponse {
+    fn matches(&self, context: &PricingContext) -> bool {
+        let date = context.start_time.date();
+        let weekday = date.weekday().number_from_monday() as i32;
+
+        self.is_active
+            && self.valid_from.is_none_or(|from| date >= from)
+            && self.valid_to.is_none_or(|to| date <= to)
+            && self
+                .weekdays
+                .as_ref()
+                .is_none_or(|days| days.contains(&weekday))
+            && in_time_band(context.start_time.time(), self.time_from, self.time_to)
+            && self
+                .premiere_weeks
+                .is_none_or(|weeks| date < context.film_start_date + Duration::weeks(weeks.into()))
+            && self
+                .format
+                .as_ref()
+                .is_none_or(|format| *format == context.format)
+            && self
+                .seat_category
+                .as_ref()
+                .is_none_or(|category| Some(category) == context.seat_category.as_ref())
+            && self
+                .min_occupancy
+                .is_none_or(|min| context.occupancy >= min)
+    }
+}
+
+fn apply_action(price: Decimal, action: &str, value: Decimal) -> Decimal {
+    let price = match action {
+        ACTION_SET => value,
+        ACTION_PERCENT => round_money(price * value / Decimal::ONE_HUNDRED),
+        _ => price + value,
+    };
+
+    price.max(Decimal::ZERO)
+}
+
+// Цена по правилам: подходящие правила по порядку, начиная с нуля.
+// None — ни одно подходящее правило не задаёт цену через "set"
+fn rule_price(
+    rules: &[PriceRuleResponse],
+    context: &PricingContext,
+) -> Option<(Decimal, Vec<PriceStep>)> {
+    let mut price = Decimal::ZERO;
+    let mut has_base = false;
+    let mut steps = Vec::new();
+
+    for rule in rules.iter().filter(|rule| rule.matches(context)) {
+        price = apply_action(price, &rule.action, rule.value);
+        has_base |= rule.action == ACTION_SET;
+        steps.push(PriceStep {
+            source: "rule".to_string(),
+            rule_id: Some(rule.rule_id),
+            name: rule.name.clone(),
+            action: rule.action.clone(),
+            value: rule.value,
+            price,
+        });
+    }
+
+    has_base.then_some((price, steps))
+}
+
+// Цена места без категории в пустом зале, без доплаты за формат.
+// Так считается ticket_price динамического сеанса, в том числе ещё не поставленного
+pub fn empty_hall_price(
+    rules: &[PriceRuleResponse],
+    start_time: NaiveDateTime,
+    film_start_date: NaiveDate,
+    format: &str,
+) -> Option<Decimal> {
+    let context = PricingContext {
+        start_time,
+        film_start_date,
+        format: format.to_string(),
+        seat_category: None,
+        occupancy: Decimal::ZERO,
+    };
+
+    rule_price(rules, &context).map(|(price, _)| price)
+}
+
+// Процент занятых мест; у сеанса без зала вместимость неизвестна, считаем 0
+fn occupancy_percent(occupancy: &Occupancy) -> Decimal {
+    match occupancy.capacity {
+        Some(capacity) if capacity > 0 => {
+            let taken = occupancy.sold + occupancy.booked + occupancy.held;
+            round_money(Decimal::from(taken * 100) / Decimal::from(capacity))
+                .min(Decimal::ONE_HUNDRED)
+        }
+        _ => Decimal::ZERO,
+    }
+}
+
+fn no_price_for_session(session_id: i32) -> AppError {
+    AppError::Conflict(format!(
+        "No active price rule sets a price for session {}",
+        session_id
+    ))
+}
+
+// Действующие правила кинотеатра в порядке применения
+pub async fn fetch_active_rules<'e>(
+    executor: impl PgExecutor<'e>,
+    cinema_id: i32,
+) -> Result<Vec<PriceRuleResponse>, AppError> {
+    let rules = sqlx::query_as!(
+        PriceRuleResponse,
+        r#"
+        SELECT
+            r.rule_id,
+            r.cinema_id,
+            r.name,
+            r.priority,
+            r.valid_from,
+            r.valid_to,
+            r.weekdays,
+            r.time_from,
+            r.time_to,
+            r.premiere_weeks,
+            r.format,
+            r.seat_category,
+            r.min_occupancy,
+            r.action,
+            r.value,
+            c.currency::text as "currency!",
+            r.is_active
+        FROM price_rule r
+        JOIN cinema c ON r.cinema_id = c.cinema_id
+        WHERE r.cinema_id = $1 AND r.is_active
+        ORDER BY r.priority, r.rule_id
+        "#,
+        cinema_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(rules)
+}
+
+async fn fetch_session_pricing<'e>(
+    executor: impl PgExecutor<'e>,
+    session_id: i32,
+) -> Result<SessionPricing, AppError> {
+    sqlx::query_as!(
+        SessionPricing,
+        r#"
+        SELECT
+            s.cinema_id,
+            s.pricing_mode,
+            s.ticket_price,
+            session_surcharge(s.cinema_id, s.format) as "surcharge!",
+            c.currency::text as "currency!",
+            (s.start_time AT TIME ZONE c.timezone) as "start_time!",
+            f.start_date as film_start_date,
+            s.format
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        JOIN film f ON s.film_id = f.film_id
+        WHERE s.session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_optional(executor)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))
+}
+
+// ticket_price динамического сеанса для списков и расписания:
+// место без категории в пустом зале, без доплаты за формат
+pub async fn dynamic_ticket_price(
+    conn: &mut PgConnection,
+    cinema_id: i32,
+    film_id: i32,
+    start_time: NaiveDateTime,
+    format: &str,
+) -> Result<Option<Decimal>, AppError> {
+    let film_start_date =
+        sqlx::query_scalar!("SELECT start_date FROM film WHERE film_id = $1", film_id)
+            .fetch_optional(&mut *conn)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Film not found".into()))?;
+
+    let rules = fetch_active_rules(&mut *conn, cinema_id).await?;
+
+    Ok(empty_hall_price(
+        &rules,
+        start_time,
+        film_start_date,
+        format,
+    ))
+}
+
+// Цены для продажи на сеанс. Динамическая цена учитывает текущую
+// заполненность, поэтому вызывать после блокировки сеанса в ensure_capacity
+pub async fn sale_pricing(
+    conn: &mut PgConnection,
+    session_id: i32,
+    seat_categories: &[Option<String>],
+) -> Result<SalePricing, AppError> {
+    let session = fetch_session_pricing(&mut *conn, session_id).await?;
+
+    if session.pricing_mode == PRICING_FIXED {
+        return Ok(SalePricing {
+            currency: session.currency,
+            list_price: session.ticket_price + session.surcharge,
+            seat_prices: Vec::new(),
+        });
+    }
+
+    let rules = fetch_active_rules(&mut *conn, session.cinema_id).await?;
+    let occupancy = occupancy_percent(&fetch_occupancy(&mut *conn, session_id).await?);
+
+    let mut categories = seat_categories.to_vec();
+    categories.push(None);
+    categories.sort();
+    categories.dedup();
+
+    let mut seat_prices = Vec::with_capacity(categories.len());
+    for category in categories {
+        let context = session.context(category.clone(), occupancy);
+        let (price, _) =
+            rule_price(&rules, &context).ok_or_else(|| no_price_for_session(session_id))?;
+        seat_prices.push((category, price + session.surcharge));
+    }
+
+    let mut pricing = SalePricing {
+        currency: session.currency,
+        list_price: Decimal::ZERO,
+        seat_prices,
+    };
+    pricing.list_price = pricing.base_price(None);
+
+    Ok(pricing)
+}
+
+// Пересчитывает ticket_price будущих динамических сеансов кинотеатра после
+// изменения правил. Изменение, после которого сеанс остаётся без цены, отклоняется
+async fn refresh_dynamic_prices(conn: &mut PgConnection, cinema_id: i32) -> Result<(), AppError> {
+    let rules = fetch_active_rules(&mut *conn, cinema_id).await?;
+
+    let sessions = sqlx::query!(
+        r#"
+        SELECT
+            s.session_id,
+            (s.start_time AT TIME ZONE c.timezone) as "start_time!",
+            f.start_date as film_start_date,
+            s.format,
+            s.ticket_price
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        JOIN film f ON s.film_id = f.film_id
+        WHERE s.cinema_id = $1
+        AND s.pricing_mode = $2
+        AND s.status = $3
+        AND s.start_time > NOW()
+        FOR UPDATE OF s
+        "#,
+        cinema_id,
+        PRICING_DYNAMIC,
+        STATUS_SCHEDULED
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for session in sessions {
+        let price = empty_hall_price(
+            &rules,
+            session.start_time,
+            session.film_start_date,
+            &session.format,
+        )
+        .ok_or_else(|| no_price_for_session(session.session_id))?;
+
+        if price != session.ticket_price {
+            sqlx::query!(
+                "UPDATE session SET ticket_price = $1 WHERE session_id = $2",
+                price,
+                session.session_id
+            )
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", value)))
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid time {}, expected HH:MM:SS", value)))
+}
+
+struct RulePeriod {
+    valid_from: Option<NaiveDate>,
+    valid_to: Option<NaiveDate>,
+    time_from: Option<NaiveTime>,
+    time_to: Option<NaiveTime>,
+}
+
+fn validate_rule(rule: &CreatePriceRuleRequest) -> Result<RulePeriod, AppError> {
+    if rule.name.trim().is_empty() {
+        return Err(AppError::InvalidInput("Rule name is required".into()));
+    }
+
+    let valid_from = rule.valid_from.as_deref().map(parse_date).transpose()?;
+    let valid_to = rule.valid_to.as_deref().map(parse_date).transpose()?;
+    if let (Some(from), Some(to)) = (valid_from, valid_to)
+        && from > to
+    {
+        return Err(AppError::InvalidInput(
+            "valid_from must not be after valid_to".into(),
+        ));
+    }
+
+    let time_from = rule.time_from.as_deref().map(parse_time).transpose()?;
+    let time_to = rule.time_to.as_deref().map(parse_time).transpose()?;
+    if time_from.is_some() != time_to.is_some() {
+        return Err(AppError::InvalidInput(
+            "time_from and time_to must be given together".into(),
+        ));
+    }
+
+    if let Some(weekdays) = &rule.weekdays
+        && (weekdays.is_empty() || weekdays.iter().any(|day| !(1..=7).contains(day)))
+    {
+        return Err(AppError::InvalidInput(
+            "Weekdays must be numbers from 1 (Monday) to 7 (Sunday)".into(),
+        ));
+    }
+    if rule.premiere_weeks.is_some_and(|weeks| weeks <= 0) {
+        return Err(AppError::InvalidInput(
+            "premiere_weeks must be positive".into(),
+        ));
+    }
+    if let Some(format) = &rule.format
+        && !SESSION_FORMATS.contains(&format.as_str())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown format {}, expected one of {:?}",
+            format, SESSION_FORMATS
+        )));
+    }
+    if let Some(category) = &rule.seat_category
+        && !SEAT_CATEGORIES.contains(&category.as_str())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown seat category {}, expected one of {:?}",
+            category, SEAT_CATEGORIES
+        )));
+    }
+    if rule
+        .min_occupancy
+        .is_some_and(|min| min < Decimal::ZERO || min > Decimal::ONE_HUNDRED)
+    {
+        return Err(AppError::InvalidInput(
+            "min_occupancy must be a percentage from 0 to 100".into(),
+        ));
+    }
+
+    match rule.action.as_str() {
+        ACTION_SET => validate_amount("Price", rule.value)?,
+        ACTION_PERCENT => {
+            validate_amount("Percent", rule.value)?;
+            if rule.value > MAX_PERCENT {
+                return Err(AppError::InvalidInput(format!(
+                    "Percent cannot exceed {}",
+                    MAX_PERCENT
+                )));
+            }
+        }
+        ACTION_ADD => validate_amount("Amount", rule.value.abs())?,
+        _ => {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown action {}, expected one of {:?}",
+                rule.action, RULE_ACTIONS
+            )));
+        }
+    }
+
+    Ok(RulePeriod {
+        valid_from,
+        valid_to,
+        time_from,
+        time_to,
+    })
+}
+
+pub async fn get_price_rules(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let rules = sqlx::query_as!(
+        PriceRuleResponse,
+        r#"
+        SELECT
+            r.rule_id,
+            r.cinema_id,
+            r.name,
+            r.priority,
+            r.valid_from,
+            r.valid_to,
+            r.weekdays,
+            r.time_from,
+            r.time_to,
+            r.premiere_weeks,
+            r.format,
+            r.seat_category,
+            r.min_occupancy,
+            r.action,
+            r.value,
+            c.currency::text as "currency!",
+            r.is_active
+        FROM price_rule r
+        JOIN cinema c ON r.cinema_id = c.cinema_id
+        WHERE r.cinema_id = $1
+        ORDER BY r.priority, r.rule_id
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(rules))
+}
+
+// Новое правило сразу пересчитывает цены будущих динамических сеансов;
+// проданные билеты сохраняют свою цену
+pub async fn create_price_rule(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    new_rule: web::Json<CreatePriceRuleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    let period = validate_rule(&new_rule)?;
+    ensure_cinema_currency(pool.get_ref(), cinema_id, &new_rule.currency).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let rule = sqlx::query_as!(
+        PriceRuleResponse,
+        r#"
+        INSERT INTO price_rule (
+            cinema_id,
+            name,
+            priority,
+            valid_from,
+            valid_to,
+            weekdays,
+            time_from,
+            time_to,
+            premiere_weeks,
+            format,
+            seat_category,
+            min_occupancy,
+            action,
+            value,
+            is_active
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+        RETURNING
+            rule_id,
+            cinema_id,
+            name,
+            priority,
+            valid_from,
+            valid_to,
+            weekdays,
+            time_from,
+            time_to,
+            premiere_weeks,
+            format,
+            seat_category,
+            min_occupancy,
+            action,
+            value,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = price_rule.cinema_id
+            ) as "currency!",
+            is_active
+        "#,
+        cinema_id,
+        new_rule.name,
+        new_rule.priority.unwrap_or(0),
+        period.valid_from,
+        period.valid_to,
+        new_rule.weekdays.as_deref(),
+        period.time_from,
+        period.time_to,
+        new_rule.premiere_weeks,
+        new_rule.format,
+        new_rule.seat_category,
+        new_rule.min_occupancy,
+        new_rule.action,
+        new_rule.value,
+        new_rule.is_active.unwrap_or(true)
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    refresh_dynamic_prices(&mut tx, cinema_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(rule))
+}
+
+pub async fn update_price_rule(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    updated_rule: web::Json<CreatePriceRuleRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, rule_id) = path.into_inner();
+    let period = validate_rule(&updated_rule)?;
+    ensure_cinema_currency(pool.get_ref(), cinema_id, &updated_rule.currency).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let rule = sqlx::query_as!(
+        PriceRuleResponse,
+        r#"
+        UPDATE price_rule
+        SET
+            name = $1,
+            priority = $2,
+            valid_from = $3,
+            valid_to = $4,
+            weekdays = $5,
+            time_from = $6,
+            time_to = $7,
+            premiere_weeks = $8,
+            format = $9,
+            seat_category = $10,
+            min_occupancy = $11,
+            action = $12,
+            value = $13,
+            is_active = $14
+        WHERE cinema_id = $15 AND rule_id = $16
+        RETURNING
+            rule_id,
+            cinema_id,
+            name,
+            priority,
+            valid_from,
+            valid_to,
+            weekdays,
+            time_from,
+            time_to,
+            premiere_weeks,
+            format,
+            seat_category,
+            min_occupancy,
+            action,
+            value,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = price_rule.cinema_id
+            ) as "currency!",
+            is_active
+        "#,
+        updated_rule.name,
+        updated_rule.priority.unwrap_or(0),
+        period.valid_from,
+        period.valid_to,
+        updated_rule.weekdays.as_deref(),
+        period.time_from,
+        period.time_to,
+        updated_rule.premiere_weeks,
+        updated_rule.format,
+        updated_rule.seat_category,
+        updated_rule.min_occupancy,
+        updated_rule.action,
+        updated_rule.value,
+        updated_rule.is_active.unwrap_or(true),
+        cinema_id,
+        rule_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Price rule not found".into()))?;
+
+    refresh_dynamic_prices(&mut tx, cinema_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(rule))
+}
+
+pub async fn delete_price_rule(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, rule_id) = path.into_inner();
+
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM price_rule WHERE cinema_id = $1 AND rule_id = $2",
+        cinema_id,
+        rule_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Price rule not found".into()));
+    }
+
+    refresh_dynamic_prices(&mut tx, cinema_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Как получилась цена билета на сеанс сейчас: ручная цена или подходящие
+// правила по порядку, затем доплата за формат и, если указан, тип билета
+pub async fn get_session_price(
+    pool: web::Data<PgPool>,
+    session_id: web::Path<i32>,
+    query: web::Query<PriceQuery>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = session_id.into_inner();
+    if let Some(category) = &query.seat_category
+        && !SEAT_CATEGORIES.contains(&category.as_str())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown seat category {}, expected one of {:?}",
+            category, SEAT_CATEGORIES
+        )));
+    }
+
+    let session = fetch_session_pricing(pool.get_ref(), session_id).await?;
+    let occupancy = occupancy_percent(&fetch_occupancy(pool.get_ref(), session_id).await?);
+
+    let mut steps = Vec::new();
+    let mut price = if session.pricing_mode == PRICING_FIXED {
+        steps.push(PriceStep {
+            source: "ticket_price".to_string(),
+            rule_id: None,
+            name: "Session ticket price".to_string(),
+            action: ACTION_SET.to_string(),
+            value: session.ticket_price,
+            price: session.ticket_price,
+        });
+        session.ticket_price
+    } else {
+        let rules = fetch_active_rules(pool.get_ref(), session.cinema_id).await?;
+        let context = session.context(query.seat_category.clone(), occupancy);
+        let (price, rule_steps) =
+            rule_price(&rules, &context).ok_or_else(|| no_price_for_session(session_id))?;
+        steps.extend(rule_steps);
+        price
+    };
+
+    if session.surcharge != Decimal::ZERO {
+        price += session.surcharge;
+        steps.push(PriceStep {
+            source: "surcharge".to_string(),
+            rule_id: None,
+            name: format!("Format surcharge ({})", session.format),
+            action: ACTION_ADD.to_string(),
+            value: session.surcharge,
+            price,
+        });
+    }
+
+    if let Some(ticket_type_id) = query.ticket_type_id {
+        let ticket_type = sqlx::query!(
+            r#"
+            SELECT name, percent, adjustment
+            FROM ticket_type
+            WHERE cinema_id = $1 AND ticket_type_id = $2 AND is_active
+            "#,
+            session.cinema_id,
+            ticket_type_id
+        )
+        .fetch_optional(pool.get_ref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Ticket type not found".into()))?;
+
+        let base = price;
+        price = apply_action(base, ACTION_PERCENT, ticket_type.percent);
+        steps.push(PriceStep {
+            source: "ticket_type".to_string(),
+            rule_id: None,
+            name: ticket_type.name.clone(),
+            action: ACTION_PERCENT.to_string(),
+            value: ticket_type.percent,
+            price,
+        });
+        if ticket_type.adjustment != Decimal::ZERO {
+            price = ticket_type_price(base, ticket_type.percent, ticket_type.adjustment);
+            steps.push(PriceStep {
+                source: "ticket_type".to_string(),
+                rule_id: None,
+                name: ticket_type.name,
+                action: ACTION_ADD.to_string(),
+                value: ticket_type.adjustment,
+                price,
+            });
+        }
+    }
+
+    Ok(HttpResponse::Ok().json(PriceExplanation {
+        session_id,
+        pricing_mode: session.pricing_mode,
+        seat_category: query.seat_category.clone(),
+        ticket_type_id: query.ticket_type_id,
+        occupancy,
+        steps,
+        price,
+        currency: session.currency,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap()
+    }
+
+    fn time(hour: u32, minute: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn rule(rule_id: i32, action: &str, value: Decimal) -> PriceRuleResponse {
+        PriceRuleResponse {
+            rule_id,
+            cinema_id: 1,
+            name: format!("Rule {}", rule_id),
+            priority: rule_id * 10,
+            valid_from: None,
+            valid_to: None,
+            weekdays: None,
+            time_from: None,
+            time_to: None,
+            premiere_weeks: None,
+            format: None,
+            seat_category: None,
+            min_occupancy: None,
+            action: action.to_string(),
+            value,
+            currency: "RUB".to_string(),
+            is_active: true,
+        }
+    }
+
+    fn context(start_time: NaiveDateTime, film_start_date: NaiveDate) -> PricingContext {
+        PricingContext {
+            start_time,
+            film_start_date,
+            format: "2d".to_string(),
+            seat_category: None,
+            occupancy: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn in_time_band_keeps_end_of_band_out() {
+        let (from, to) = (Some(time(10, 0)), Some(time(18, 0)));
+
+        assert!(in_time_band(time(10, 0), from, to));
+        assert!(in_time_band(time(17, 59), from, to));
+        assert!(!in_time_band(time(18, 0), from, to));
+        assert!(!in_time_band(time(9, 59), from, to));
+        assert!(in_time_band(time(3, 0), None, None));
+    }
+
+    #[test]
+    fn in_time_band_wraps_past_midnight() {
+        let (from, to) = (Some(time(22, 0)), Some(time(2, 0)));
+
+        assert!(in_time_band(time(22, 0), from, to));
+        assert!(in_time_band(time(23, 30), from, to));
+        assert!(in_time_band(time(0, 0), from, to));
+        assert!(in_time_band(time(1, 59), from, to));
+        assert!(!in_time_band(time(2, 0), from, to));
+        assert!(!in_time_band(time(12, 0), from, to));
+    }
+
+    #[test]
+    fn rule_price_folds_rules_in_priority_order() {
+        let rules = [
+            rule(1, ACTION_SET, Decimal::new(300, 0)),
+            rule(2, ACTION_PERCENT, Decimal::new(150, 0)),
+            rule(3, ACTION_ADD, Decimal::new(-50, 0)),
+        ];
+        let context = context(day().and_time(time(19, 0)), day());
+
+        let (price, steps) = rule_price(&rules, &context).unwrap();
+
+        assert_eq!(price, Decimal::new(400, 0));
+        let prices: Vec<Decimal> = steps.iter().map(|step| step.price).collect();
+        assert_eq!(
+            prices,
+            vec![
+                Decimal::new(300, 0),
+                Decimal::new(450, 0),
+                Decimal::new(400, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn rule_price_set_overrides_earlier_rules() {
+        let rules = [
+            rule(1, ACTION_ADD, Decimal::new(100, 0)),
+            rule(2, ACTION_SET, Decimal::new(250, 0)),
+            rule(3, ACTION_ADD, Decimal::new(-400, 0)),
+        ];
+        let context = context(day().and_time(time(19, 0)), day());
+
+        let (price, steps) = rule_price(&rules, &context).unwrap();
+
+        // Скидка больше цены не уводит её ниже нуля
+        assert_eq!(price, Decimal::ZERO);
+        assert_eq!(steps[1].price, Decimal::new(250, 0));
+    }
+
+    #[test]
+    fn rule_price_without_set_rule_is_none() {
+        let mut evening_set = rule(1, ACTION_SET, Decimal::new(300, 0));
+        evening_set.time_from = Some(time(18, 0));
+        evening_set.time_to = Some(time(23, 59));
+        let rules = [
+            evening_set,
+            rule(2, ACTION_PERCENT, Decimal::new(80, 0)),
+            rule(3, ACTION_ADD, Decimal::new(50, 0)),
+        ];
+        let morning = context(day().and_time(time(11, 0)), day());
+
+        assert!(rule_price(&rules, &morning).is_none());
+        assert_eq!(
+            empty_hall_price(&rules, day().and_time(time(11, 0)), day(), "2d"),
+            None
+        );
+    }
+
+    #[test]
+    fn premiere_weeks_count_from_film_start_date() {
+        let mut premiere = rule(1, ACTION_SET, Decimal::new(500, 0));
+        premiere.premiere_weeks = Some(2);
+        let film_start_date = day();
+
+        let last_day = context(
+            (day() + Duration::days(13)).and_time(time(19, 0)),
+            film_start_date,
+        );
+        let after = context(
+            (day() + Duration::days(14)).and_time(time(19, 0)),
+            film_start_date,
+        );
+
+        assert!(premiere.matches(&last_day));
+        assert!(!premiere.matches(&after));
+    }
+}
@@ -1,9 +1,23 @@
 use actix_web::web;
 
+mod availability;
 mod bookings;
 mod cinemas;
+mod customers;
+mod employees;
+mod film_orders;
 mod films;
+mod halls;
+mod opening_hours;
+mod holds;
+mod money;
+mod pricing;
+mod schedule_proposals;
+mod schedule_templates;
+mod seats;
 mod sessions;
+mod surcharges;
+mod ticket_types;
 mod tickets;
 
 pub fn config(cfg: &mut web::ServiceConfig) {
@@ -16,7 +30,95 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                     .route("", web::post().to(cinemas::create_cinema))
                     .route("/{id}", web::get().to(cinemas::get_cinema))
                     .route("/{id}", web::put().to(cinemas::update_cinema))
-                    .route("/{id}", web::delete().to(cinemas::delete_cinema)),
+                    .route("/{id}", web::delete().to(cinemas::delete_cinema))
+                    .route(
+                        "/{id}/employees",
+                        web::get().to(employees::get_cinema_employees),
+                    )
+                    .route(
+                        "/{id}/employees",
+                        web::post().to(employees::create_cinema_employee),
+                    )
+                    .route(
+                        "/{id}/hours",
+                        web::get().to(opening_hours::get_cinema_hours),
+                    )
+                    .route(
+                        "/{id}/hours/weekly",
+                        web::get().to(opening_hours::get_weekly_hours),
+                    )
+                    .route(
+                        "/{id}/hours/weekly",
+                        web::put().to(opening_hours::update_weekly_hours),
+                    )
+                    .route(
+                        "/{id}/hours/exceptions",
+                        web::post().to(opening_hours::upsert_hours_exception),
+                    )
+                    .route(
+                        "/{id}/hours/exceptions/{date}",
+                        web::delete().to(opening_hours::delete_hours_exception),
+                    )
+                    .route(
+                        "/{id}/surcharges",
+                        web::get().to(surcharges::get_format_surcharges),
+                    )
+                    .route(
+                        "/{id}/surcharges",
+                        web::put().to(surcharges::update_format_surcharges),
+                    )
+                    .route(
+                        "/{id}/price-rules",
+                        web::get().to(pricing::get_price_rules),
+                    )
+                    .route(
+                        "/{id}/price-rules",
+                        web::post().to(pricing::create_price_rule),
+                    )
+                    .route(
+                        "/{id}/price-rules/{rule_id}",
+                        web::put().to(pricing::update_price_rule),
+                    )
+                    .route(
+                        "/{id}/price-rules/{rule_id}",
+                        web::delete().to(pricing::delete_price_rule),
+                    )
+                    .route(
+                        "/{id}/ticket-types",
+                        web::get().to(ticket_types::get_ticket_types),
+                    )
+                    .route(
+                        "/{id}/ticket-types",
+                        web::post().to(ticket_types::create_ticket_type),
+                    )
+                    .route(
+                        "/{id}/ticket-types/{ticket_type_id}",
+                        web::put().to(ticket_types::update_ticket_type),
+                    )
+                    .route(
+                        "/{id}/ticket-types/{ticket_type_id}",
+                        web::delete().to(ticket_types::delete_ticket_type),
+                    )
+                    .route("/{id}/halls", web::get().to(halls::get_halls))
+                    .route("/{id}/halls", web::post().to(halls::create_hall))
+                    .route("/{id}/halls/{hall_id}", web::get().to(halls::get_hall))
+                    .route("/{id}/halls/{hall_id}", web::put().to(halls::update_hall))
+                    .route(
+                        "/{id}/halls/{hall_id}",
+                        web::delete().to(halls::delete_hall),
+                    )
+                    .route(
+                        "/{id}/halls/{hall_id}/seats",
+                        web::get().to(seats::get_seat_map),
+                    )
+                    .route(
+                        "/{id}/halls/{hall_id}/seats",
+                        web::put().to(seats::update_seat_map),
+                    )
+                    .route(
+                        "/{id}/halls/{hall_id}/seats",
+                        web::delete().to(seats::delete_seat_map),
+                    ),
             )
             // Фильмы
             .service(
@@ -28,15 +130,86 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                     .route("/{id}", web::put().to(films::update_film))
                     .route("/{id}", web::delete().to(films::delete_film)),
             )
+            // Заказы фильмов у дистрибьюторов
+            .service(
+                web::scope("/film-orders")
+                    .route("", web::get().to(film_orders::get_film_orders))
+                    .route("", web::post().to(film_orders::create_film_order))
+                    .route(
+                        "/{id}/deliver",
+                        web::put().to(film_orders::deliver_film_order),
+                    )
+                    .route(
+                        "/{id}/return",
+                        web::put().to(film_orders::return_film_order),
+                    )
+                    .route(
+                        "/{id}/cancel",
+                        web::put().to(film_orders::cancel_film_order),
+                    )
+                    .route("/{id}", web::get().to(film_orders::get_film_order)),
+            )
             // Сеансы
             .service(
                 web::scope("/sessions")
                     .route("", web::get().to(sessions::get_sessions))
                     .route("", web::post().to(sessions::create_session))
                     .route("/upcoming", web::get().to(sessions::get_upcoming_sessions))
+                    .route("/now", web::get().to(sessions::get_current_sessions))
+                    .route(
+                        "/{id}/availability",
+                        web::get().to(availability::get_session_availability),
+                    )
+                    .route("/{id}/price", web::get().to(pricing::get_session_price))
+                    .route("/{id}/cancel", web::post().to(sessions::cancel_session))
                     .route("/{id}", web::get().to(sessions::get_session))
+                    .route("/{id}", web::put().to(sessions::update_session))
                     .route("/{id}", web::delete().to(sessions::delete_session)),
             )
+            // Шаблоны расписания
+            .service(
+                web::scope("/schedule-templates")
+                    .route(
+                        "",
+                        web::get().to(schedule_templates::get_schedule_templates),
+                    )
+                    .route(
+                        "",
+                        web::post().to(schedule_templates::create_schedule_template),
+                    )
+                    .route(
+                        "/{id}/generate",
+                        web::post().to(schedule_templates::generate_schedule),
+                    )
+                    .route(
+                        "/{id}",
+                        web::get().to(schedule_templates::get_schedule_template),
+                    )
+                    .route(
+                        "/{id}",
+                        web::delete().to(schedule_templates::delete_schedule_template),
+                    ),
+            )
+            // Предложения недельного расписания
+            .service(
+                web::scope("/schedule-proposals")
+                    .route(
+                        "",
+                        web::post().to(schedule_proposals::create_schedule_proposal),
+                    )
+                    .route(
+                        "/{id}/commit",
+                        web::post().to(schedule_proposals::commit_schedule_proposal),
+                    )
+                    .route(
+                        "/{id}/items/{item_id}",
+                        web::delete().to(schedule_proposals::delete_proposal_item),
+                    )
+                    .route(
+                        "/{id}",
+                        web::get().to(schedule_proposals::get_schedule_proposal),
+                    ),
+            )
             // Продажи билетов
             .service(
                 web::scope("/tickets")
@@ -44,6 +217,43 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                     .route("/stats", web::get().to(tickets::get_sales_stats))
                     .route("/{id}", web::get().to(tickets::get_ticket_sale)),
             )
+            // Клиенты
+            .service(
+                web::scope("/customers")
+                    .route("", web::get().to(customers::get_customers))
+                    .route("", web::post().to(customers::create_customer))
+                    .route(
+                        "/phone/{phone}",
+                        web::get().to(customers::get_customer_by_phone),
+                    )
+                    .route("/{id}", web::get().to(customers::get_customer))
+                    .route("/{id}", web::put().to(customers::update_customer))
+                    .route("/{id}", web::delete().to(customers::delete_customer)),
+            )
+            // Сотрудники
+            .service(
+                web::scope("/employees")
+                    .route("", web::get().to(employees::get_employees))
+                    .route("", web::post().to(employees::create_employee))
+                    .route(
+                        "/{id}/transfer",
+                        web::put().to(employees::transfer_employee),
+                    )
+                    .route(
+                        "/{id}/deactivate",
+                        web::put().to(employees::deactivate_employee),
+                    )
+                    .route("/{id}", web::get().to(employees::get_employee))
+                    .route("/{id}", web::put().to(employees::update_employee)),
+            )
+            // Временные удержания мест на время оплаты
+            .service(
+                web::scope("/holds")
+                    .route("", web::post().to(holds::create_hold))
+                    .route("/{id}/extend", web::put().to(holds::extend_hold))
+                    .route("/{id}", web::get().to(holds::get_hold))
+                    .route("/{id}", web::delete().to(holds::release_hold)),
+            )
             // Бронирования
             .service(
                 web::scope("/bookings")
@@ -0,0 +1,987 @@
+use std::collections::HashMap;
+
+use actix_web::{HttpResponse, web};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Timelike};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::errors::AppError;
+use crate::halls::supports_format;
+use crate::money::{ensure_cinema_currency, round_money, validate_amount};
+use crate::opening_hours::{opening_window, resolve_hours};
+use crate::pricing::{PriceRuleResponse, empty_hall_price, fetch_active_rules};
+use crate::sessions::{SessionAttributes, SessionDraft, schedule_session};
+
+pub const STATUS_DRAFT: &str = "draft";
+pub const STATUS_COMMITTED: &str = "committed";
+
+// За сколько прошедших дней берётся статистика заполняемости
+const HISTORY_DAYS: i32 = 28;
+// Заполняемость фильма без истории показов, если у кинотеатра нет и общей статистики
+const DEFAULT_OCCUPANCY: f64 = 0.3;
+// Каждый следующий показ фильма в тот же день собирает меньше зрителей
+const SAME_DAY_DECAY: f64 = 0.85;
+// Начало сеанса округляется вверх до этого шага
+const SLOT_STEP_MINUTES: i64 = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleProposalRequest {
+    pub cinema_id: i32,
+    pub week_start: String, // Формат "YYYY-MM-DD", первый из семи дней
+    pub ticket_price: Option<Decimal>, // None — цену сеансов дают правила кинотеатра
+    pub currency: String,   // должна совпадать с валютой кинотеатра
+    #[serde(flatten)]
+    pub attributes: SessionAttributes,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProposalItemResponse {
+    pub item_id: i32,
+    pub film_id: i32,
+    pub film_title: String,
+    pub hall_id: i32,
+    pub hall_name: String,
+    pub start_time: String, // "YYYY-MM-DD HH:MM:SS", местное время кинотеатра
+    pub expected_tickets: i32,
+    pub expected_revenue: Decimal,
+    pub session_id: Option<i32>, // заполняется при утверждении
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleProposalResponse {
+    pub proposal_id: i32,
+    pub cinema_id: i32,
+    pub week_start: NaiveDate,
+    pub ticket_price: Option<Decimal>,
+    pub currency: String,
+    pub format: String,
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+    pub status: String, // "draft", "committed"
+    pub expected_revenue: Decimal,
+    pub warnings: Vec<String>,
+    pub items: Vec<ProposalItemResponse>,
+}
+
+#[derive(Debug)]
+struct HallSlot {
+    hall_id: i32,
+    capacity: i32,
+    busy: Vec<(NaiveDateTime, NaiveDateTime)>,
+}
+
+#[derive(Debug)]
+struct CandidateFilm {
+    film_id: i32,
+    title: String,
+    duration_minutes: i32,
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    copy_from: NaiveDate,
+    min_screenings: i32, // минимум показов в неделю по договору
+    occupancy: f64,
+    planned: i32,
+}
+
+impl CandidateFilm {
+    fn can_be_screened_on(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date && self.copy_from <= date
+    }
+}
+
+// Откуда берётся цена сеансов черновика
+#[derive(Debug)]
+enum PlanPricing {
+    Fixed(Decimal),                // ticket_price из запроса
+    Rules(Vec<PriceRuleResponse>), // действующие правила кинотеатра
+}
+
+#[derive(Debug)]
+struct PlanSettings {
+    ad_minutes: i32,
+    cleaning_minutes: i32,
+    format: String,
+    surcharge: Decimal, // доплата кинотеатра за формат черновика
+    pricing: PlanPricing,
+}
+
+impl PlanSettings {
+    // Цена билета, которую получит созданный сеанс: фиксированная или
+    // по правилам для пустого зала, с доплатой за формат.
+    // None — ни одно правило не задаёт цену, такой сеанс не создать
+    fn session_price(&self, film: &CandidateFilm, start: NaiveDateTime) -> Option<Decimal> {
+        let price = match &self.pricing {
+            PlanPricing::Fixed(price) => Some(*price),
+            PlanPricing::Rules(rules) => {
+                empty_hall_price(rules, start, film.start_date, &self.format)
+            }
+        };
+
+        price.map(|price| price + self.surcharge)
+    }
+}
+
+#[derive(Debug)]
+struct PlannedItem {
+    film_id: i32,
+    hall_id: i32,
+    start_time: NaiveDateTime,
+    expected_tickets: i32,
+    expected_revenue: Decimal,
+}
+
+fn round_up_to_slot(time: NaiveDateTime) -> NaiveDateTime {
+    let step = SLOT_STEP_MINUTES * 60;
+    let seconds = time.and_utc().timestamp();
+    let rounded = (seconds + step - 1).div_euclid(step) * step;
+
+    DateTime::from_timestamp(rounded, 0)
+        .map(|t| t.naive_utc())
+        .unwrap_or(time)
+}
+
+// Пересекается ли [from, to) с каким-нибудь занятым интервалом зала
+fn is_busy(
+    busy: &[(NaiveDateTime, NaiveDateTime)],
+    from: NaiveDateTime,
+    to: NaiveDateTime,
+) -> bool {
+    busy.iter()
+        .any(|(starts, ends)| *starts < to && *ends > from)
+}
+
+// Жадное заполнение залов по дням. В каждом зале сеансы идут подряд
+// с начала рабочего дня, на каждое место выбирается фильм с наибольшей
+// ожидаемой выручкой на минуту занятости зала. Фильмы, которые не добирают
+// минимума показов по договору, равномерно распределяются по оставшимся дням
+// и ставятся в первую очередь
+fn plan_week(
+    days: &[(NaiveDate, NaiveDateTime, NaiveDateTime)],
+    halls: &mut [HallSlot],
+    films: &mut [CandidateFilm],
+    hour_factors: &HashMap<i32, f64>,
+    settings: &PlanSettings,
+) -> Vec<PlannedItem> {
+    let PlanSettings {
+        ad_minutes,
+        cleaning_minutes,
+        ..
+    } = *settings;
+    let mut items = Vec::new();
+
+    for (day_index, (date, opens, closes)) in days.iter().enumerate() {
+        let quotas: Vec<i32> = films
+            .iter()
+            .map(|film| {
+                let need = film.min_screenings - film.planned;
+                let days_left = days[day_index..]
+                    .iter()
+                    .filter(|(d, _, _)| film.can_be_screened_on(*d))
+                    .count() as i32;
+                if need > 0 && days_left > 0 && film.can_be_screened_on(*date) {
+                    (need + days_left - 1) / days_left
+                } else {
+                    0
+                }
+            })
+            .collect();
+        let mut today = vec![0; films.len()];
+        let mut cursors = vec![*opens; halls.len()];
+        let mut open = vec![true; halls.len()];
+
+        while let Some(h) = (0..halls.len())
+            .filter(|&i| open[i])
+            .min_by_key(|&i| cursors[i])
+        {
+            let start = cursors[h];
+            let hour_factor = hour_factors
+                .get(&(start.hour() as i32))
+                .copied()
+                .unwrap_or(1.0);
+
+            // (индекс фильма, приоритетный ли он, ожидаемые зрители, выручка, выручка на минуту)
+            let mut best: Option<(usize, bool, i32, Decimal, Decimal)> = None;
+            for (f, film) in films.iter().enumerate() {
+                let screen_until =
+                    start + Duration::minutes((ad_minutes + film.duration_minutes) as i64);
+                let busy_until = screen_until + Duration::minutes(cleaning_minutes as i64);
+                if !film.can_be_screened_on(start.date())
+                    || screen_until > *closes
+                    || is_busy(&halls[h].busy, start, busy_until)
+                {
+                    continue;
+                }
+                let Some(price) = settings.session_price(film, start) else {
+                    continue;
+                };
+
+                let share = (film.occupancy * hour_factor * SAME_DAY_DECAY.powi(today[f])).min(1.0);
+                let tickets = (share * halls[h].capacity as f64).round() as i32;
+                let revenue = round_money(Decimal::from(tickets) * price);
+                let per_minute =
+                    revenue / Decimal::from(ad_minutes + film.duration_minutes + cleaning_minutes);
+                let priority = today[f] < quotas[f];
+
+                let better = match best {
+                    None => true,
+                    Some((_, best_priority, _, _, best_per_minute)) => {
+                        (priority, per_minute) > (best_priority, best_per_minute)
+                    }
+                };
+                if better {
+                    best = Some((f, priority, tickets, revenue, per_minute));
+                }
+            }
+
+            match best {
+                Some((f, _, tickets, revenue, _)) => {
+                    let film = &mut films[f];
+                    let busy_until = start
+                        + Duration::minutes(
+                            (ad_minutes + film.duration_minutes + cleaning_minutes) as i64,
+                        );
+                    halls[h].busy.push((start, busy_until));
+                    cursors[h] = round_up_to_slot(busy_until);
+                    film.planned += 1;
+                    today[f] += 1;
+                    items.push(PlannedItem {
+                        film_id: film.film_id,
+                        hall_id: halls[h].hall_id,
+                        start_time: start,
+                        expected_tickets: tickets,
+                        expected_revenue: revenue,
+                    });
+                }
+                None => {
+                    // На это время ничего не помещается или правила не дают цены:
+                    // пропускаем ближайший занятый интервал зала, а если его нет —
+                    // пробуем следующий слот, пока зал не закроется
+                    let next = halls[h]
+                        .busy
+                        .iter()
+                        .filter(|(_, ends)| *ends > start)
+                        .map(|(_, ends)| round_up_to_slot(*ends))
+                        .min()
+                        .unwrap_or(start + Duration::minutes(SLOT_STEP_MINUTES));
+                    if next < *closes {
+                        cursors[h] = next;
+                    } else {
+                        open[h] = false;
+                    }
+                }
+            }
+        }
+    }
+
+    items
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", value)))
+}
+
+async fn fetch_proposal(
+    pool: &PgPool,
+    proposal_id: i32,
+) -> Result<ScheduleProposalResponse, AppError> {
+    let proposal = sqlx::query!(
+        r#"
+        SELECT
+            p.proposal_id,
+            p.cinema_id,
+            p.week_start,
+            p.ticket_price,
+            c.currency::text as "currency!",
+            p.format,
+            p.audio_language,
+            p.subtitle_language,
+            p.audio_description,
+            p.status,
+            p.warnings
+        FROM schedule_proposal p
+        JOIN cinema c ON p.cinema_id = c.cinema_id
+        WHERE p.proposal_id = $1
+        "#,
+        proposal_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Schedule proposal not found".into()))?;
+
+    let items = sqlx::query_as!(
+        ProposalItemResponse,
+        r#"
+        SELECT
+            i.item_id,
+            i.film_id,
+            f.title as film_title,
+            i.hall_id,
+            h.name as hall_name,
+            i.start_time::text as "start_time!",
+            i.expected_tickets,
+            i.expected_revenue,
+            i.session_id
+        FROM schedule_proposal_item i
+        JOIN film f ON i.film_id = f.film_id
+        JOIN hall h ON i.hall_id = h.hall_id
+        WHERE i.proposal_id = $1
+        ORDER BY i.start_time, h.name
+        "#,
+        proposal_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(ScheduleProposalResponse {
+        proposal_id: proposal.proposal_id,
+        cinema_id: proposal.cinema_id,
+        week_start: proposal.week_start,
+        ticket_price: proposal.ticket_price,
+        currency: proposal.currency,
+        format: proposal.format,
+        audio_language: proposal.audio_language,
+        subtitle_language: proposal.subtitle_language,
+        audio_description: proposal.audio_description,
+        status: proposal.status,
+        expected_revenue: items.iter().map(|i| i.expected_revenue).sum(),
+        warnings: proposal.warnings,
+        items,
+    })
+}
+
+pub async fn get_schedule_proposal(
+    pool: web::Data<PgPool>,
+    proposal_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let proposal = fetch_proposal(pool.get_ref(), proposal_id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(proposal))
+}
+
+// Строит и сохраняет черновик сетки сеансов на семь дней.
+// Учитываются фильмы в прокате с заказанной копией и минимумом показов
+// по договору, активные залы под формат черновика, часы работы,
+// уже поставленные сеансы, заполняемость за последние недели и цены кинотеатра
+pub async fn create_schedule_proposal(
+    pool: web::Data<PgPool>,
+    request: web::Json<CreateScheduleProposalRequest>,
+) -> Result<HttpResponse, AppError> {
+    let week_start = parse_date(&request.week_start)?;
+    let week_end = week_start + Duration::days(6);
+
+    if let Some(ticket_price) = request.ticket_price {
+        validate_amount("Ticket price", ticket_price)?;
+    }
+    let attributes = &request.attributes;
+    attributes.validate()?;
+
+    ensure_cinema_currency(pool.get_ref(), request.cinema_id, &request.currency).await?;
+
+    let cinema = sqlx::query!(
+        r#"
+        SELECT
+            ad_minutes,
+            cleaning_minutes,
+            session_surcharge(cinema_id, $2) as "surcharge!"
+        FROM cinema
+        WHERE cinema_id = $1
+        "#,
+        request.cinema_id,
+        attributes.format
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let pricing = match request.ticket_price {
+        Some(ticket_price) => PlanPricing::Fixed(ticket_price),
+        None => PlanPricing::Rules(fetch_active_rules(pool.get_ref(), request.cinema_id).await?),
+    };
+
+    let days: Vec<_> = resolve_hours(pool.get_ref(), request.cinema_id, week_start, week_end)
+        .await?
+        .iter()
+        .filter_map(|day| opening_window(day).map(|(opens, closes)| (day.date, opens, closes)))
+        .collect();
+
+    let mut halls: Vec<HallSlot> = sqlx::query!(
+        r#"
+        SELECT hall_id, capacity, screen_type
+        FROM hall
+        WHERE cinema_id = $1 AND is_active
+        ORDER BY hall_id
+        "#,
+        request.cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?
+    .into_iter()
+    .filter(|row| supports_format(&row.screen_type, &attributes.format))
+    .map(|row| HallSlot {
+        hall_id: row.hall_id,
+        capacity: row.capacity,
+        busy: Vec::new(),
+    })
+    .collect();
+
+    if halls.is_empty() {
+        return Err(AppError::InvalidInput(format!(
+            "Cinema has no active halls for {} sessions",
+            attributes.format
+        )));
+    }
+
+    // Уже поставленные сеансы недели занимают залы и засчитываются в минимум показов
+    let existing = sqlx::query!(
+        r#"
+        SELECT
+            s.hall_id as "hall_id!",
+            s.film_id,
+            (s.start_time AT TIME ZONE c.timezone) as "starts!",
+            (
+                s.start_time
+                + make_interval(mins => c.ad_minutes + f.duration_minutes + c.cleaning_minutes)
+            ) AT TIME ZONE c.timezone as "ends!"
+        FROM session s
+        JOIN film f ON s.film_id = f.film_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE s.cinema_id = $1
+        AND s.hall_id IS NOT NULL
+        AND s.status = 'scheduled'
+        AND s.start_time >= ($2::date - 1)::timestamp AT TIME ZONE c.timezone
+        AND s.start_time < ($3::date + 2)::timestamp AT TIME ZONE c.timezone
+        "#,
+        request.cinema_id,
+        week_start,
+        week_end
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    for session in &existing {
+        if let Some(hall) = halls.iter_mut().find(|h| h.hall_id == session.hall_id) {
+            hall.busy.push((session.starts, session.ends));
+        }
+    }
+
+    // Заполняемость прошедших сеансов кинотеатра: по фильмам и по часу начала
+    let history = sqlx::query!(
+        r#"
+        SELECT
+            s.film_id,
+            EXTRACT(HOUR FROM s.start_time AT TIME ZONE c.timezone)::int as "hour!",
+            LEAST(
+                (
+                    SELECT COALESCE(SUM(ts.ticket_count), 0)
+                    FROM ticket_sale ts
+                    WHERE ts.session_id = s.session_id
+                )::float8 / h.capacity,
+                1
+            ) as "occupancy!"
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        JOIN hall h ON s.hall_id = h.hall_id
+        WHERE s.cinema_id = $1
+        AND s.status = 'scheduled'
+        AND h.capacity > 0
+        AND s.start_time < NOW()
+        AND s.start_time >= NOW() - make_interval(days => $2)
+        "#,
+        request.cinema_id,
+        HISTORY_DAYS
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    let mut by_film: HashMap<i32, (f64, i32)> = HashMap::new();
+    let mut by_hour: HashMap<i32, (f64, i32)> = HashMap::new();
+    for row in &history {
+        let film = by_film.entry(row.film_id).or_default();
+        film.0 += row.occupancy;
+        film.1 += 1;
+        let hour = by_hour.entry(row.hour).or_default();
+        hour.0 += row.occupancy;
+        hour.1 += 1;
+    }
+    let overall = if history.is_empty() {
+        DEFAULT_OCCUPANCY
+    } else {
+        history.iter().map(|row| row.occupancy).sum::<f64>() / history.len() as f64
+    };
+    // Во сколько раз сеансы, начинающиеся в этот час, заполняются лучше среднего
+    let hour_factors: HashMap<i32, f64> = if overall > 0.0 {
+        by_hour
+            .into_iter()
+            .map(|(hour, (sum, count))| (hour, sum / count as f64 / overall))
+            .collect()
+    } else {
+        HashMap::new()
+    };
+
+    let mut films: Vec<CandidateFilm> = sqlx::query!(
+        r#"
+        SELECT
+            f.film_id,
+            f.title,
+            f.duration_minutes,
+            f.start_date,
+            f.end_date,
+            MIN(fo.delivery_date) as "copy_from!",
+            MAX(fo.min_screenings) as "min_screenings!"
+        FROM film f
+        JOIN film_order fo ON fo.film_id = f.film_id
+        WHERE fo.cinema_id = $1
+        AND fo.status IN ('ordered', 'delivered')
+        AND NOT f.duration_estimated
+        AND f.start_date <= $3
+        AND f.end_date >= $2
+        GROUP BY f.film_id
+        ORDER BY f.film_id
+        "#,
+        request.cinema_id,
+        week_start,
+        week_end
+    )
+    .fetch_all(pool.get_ref())
+    .await?
+    .into_iter()
+    .map(|row| CandidateFilm {
+        film_id: row.film_id,
+        title: row.title,
+        duration_minutes: row.duration_minutes,
+        start_date: row.start_date,
+        end_date: row.end_date,
+        copy_from: row.copy_from,
+        min_screenings: row.min_screenings,
+        occupancy: by_film
+            .get(&row.film_id)
+            .map(|(sum, count)| sum / *count as f64)
+            .unwrap_or(overall),
+        planned: existing
+            .iter()
+            .filter(|s| {
+                s.film_id == row.film_id
+                    && s.starts.date() >= week_start
+                    && s.starts.date() <= week_end
+            })
+            .count() as i32,
+    })
+    .collect();
+
+    if films.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No films in release with a copy ordered for this week".into(),
+        ));
+    }
+
+    let planned = plan_week(
+        &days,
+        &mut halls,
+        &mut films,
+        &hour_factors,
+        &PlanSettings {
+            ad_minutes: cinema.ad_minutes,
+            cleaning_minutes: cinema.cleaning_minutes,
+            format: attributes.format.clone(),
+            surcharge: cinema.surcharge,
+            pricing,
+        },
+    );
+
+    let warnings: Vec<String> = films
+        .iter()
+        .filter(|film| film.planned < film.min_screenings)
+        .map(|film| {
+            format!(
+                "{}: {} of {} contracted screenings fit into the week",
+                film.title, film.planned, film.min_screenings
+            )
+        })
+        .collect();
+
+    let mut tx = pool.begin().await?;
+
+    let proposal_id = sqlx::query_scalar!(
+        r#"
+        INSERT INTO schedule_proposal (
+            cinema_id,
+            week_start,
+            ticket_price,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description,
+            warnings
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING proposal_id
+        "#,
+        request.cinema_id,
+        week_start,
+        request.ticket_price,
+        attributes.format,
+        attributes.audio_language,
+        attributes.subtitle_language,
+        attributes.audio_description,
+        &warnings
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    let film_ids: Vec<i32> = planned.iter().map(|i| i.film_id).collect();
+    let hall_ids: Vec<i32> = planned.iter().map(|i| i.hall_id).collect();
+    let start_times: Vec<NaiveDateTime> = planned.iter().map(|i| i.start_time).collect();
+    let tickets: Vec<i32> = planned.iter().map(|i| i.expected_tickets).collect();
+    let revenues: Vec<Decimal> = planned.iter().map(|i| i.expected_revenue).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO schedule_proposal_item
+            (proposal_id, film_id, hall_id, start_time, expected_tickets, expected_revenue)
+        SELECT $1, * FROM UNNEST($2::int[], $3::int[], $4::timestamp[], $5::int[], $6::numeric[])
+        "#,
+        proposal_id,
+        &film_ids,
+        &hall_ids,
+        &start_times,
+        &tickets,
+        &revenues
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let proposal = fetch_proposal(pool.get_ref(), proposal_id).await?;
+
+    Ok(HttpResponse::Created().json(proposal))
+}
+
+// Убирает сеанс из черновика перед утверждением
+pub async fn delete_proposal_item(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (proposal_id, item_id) = path.into_inner();
+
+    let status = sqlx::query_scalar!(
+        "SELECT status FROM schedule_proposal WHERE proposal_id = $1",
+        proposal_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await?
+    .ok_or_else(|| AppError::NotFound("Schedule proposal not found".into()))?;
+
+    if status != STATUS_DRAFT {
+        return Err(AppError::Conflict(
+            "Committed proposal cannot be changed".into(),
+        ));
+    }
+
+    let result = sqlx::query!(
+        "DELETE FROM schedule_proposal_item WHERE proposal_id = $1 AND item_id = $2",
+        proposal_id,
+        item_id
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Proposal item not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Создаёт все сеансы черновика в одной транзакции с обычными проверками.
+// Если хоть один сеанс не проходит (например, зал успели занять вручную),
+// не создаётся ни один
+pub async fn commit_schedule_proposal(
+    pool: web::Data<PgPool>,
+    proposal_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let proposal_id = proposal_id.into_inner();
+
+    let mut tx = pool.begin().await?;
+
+    let proposal = sqlx::query!(
+        r#"
+        SELECT
+            cinema_id,
+            ticket_price,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description,
+            status
+        FROM schedule_proposal
+        WHERE proposal_id = $1
+        FOR UPDATE
+        "#,
+        proposal_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Schedule proposal not found".into()))?;
+
+    if proposal.status != STATUS_DRAFT {
+        return Err(AppError::Conflict("Proposal is already committed".into()));
+    }
+
+    let items = sqlx::query!(
+        r#"
+        SELECT item_id, film_id, hall_id, start_time
+        FROM schedule_proposal_item
+        WHERE proposal_id = $1
+        ORDER BY start_time, hall_id
+        "#,
+        proposal_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if items.is_empty() {
+        return Err(AppError::InvalidInput("Proposal has no sessions".into()));
+    }
+
+    let attributes = SessionAttributes {
+        format: proposal.format,
+        audio_language: proposal.audio_language,
+        subtitle_language: proposal.subtitle_language,
+        audio_description: proposal.audio_description,
+    };
+    let mut problems = Vec::new();
+
+    for item in &items {
+        let draft = SessionDraft {
+            film_id: item.film_id,
+            cinema_id: proposal.cinema_id,
+            hall_id: Some(item.hall_id),
+            start_time: item.start_time,
+            ticket_price: proposal.ticket_price,
+            attributes: attributes.clone(),
+        };
+        let scheduled = schedule_session(&mut tx, &draft).await;
+
+        match scheduled {
+            Ok(session) => {
+                sqlx::query!(
+                    "UPDATE schedule_proposal_item SET session_id = $1 WHERE item_id = $2",
+                    session.session_id,
+                    item.item_id
+                )
+                .execute(&mut *tx)
+                .await?;
+            }
+            Err(AppError::InvalidInput(problem) | AppError::Conflict(problem)) => {
+                problems.push(format!("{}: {}", item.start_time, problem));
+            }
+            Err(err) => return Err(err),
+        }
+    }
+
+    if let Some(first) = problems.first() {
+        tx.rollback().await?;
+        return Err(AppError::Conflict(format!(
+            "{} of {} proposed sessions cannot be created, first at {}",
+            problems.len(),
+            items.len(),
+            first
+        )));
+    }
+
+    sqlx::query!(
+        "UPDATE schedule_proposal SET status = $1 WHERE proposal_id = $2",
+        STATUS_COMMITTED,
+        proposal_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let proposal = fetch_proposal(pool.get_ref(), proposal_id).await?;
+
+    Ok(HttpResponse::Ok().json(proposal))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveTime;
+
+    use super::*;
+
+    fn day() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 3).unwrap()
+    }
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        day().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn film(film_id: i32, occupancy: f64, min_screenings: i32) -> CandidateFilm {
+        CandidateFilm {
+            film_id,
+            title: format!("Film {}", film_id),
+            duration_minutes: 90,
+            start_date: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2025, 4, 30).unwrap(),
+            copy_from: NaiveDate::from_ymd_opt(2025, 2, 1).unwrap(),
+            min_screenings,
+            occupancy,
+            planned: 0,
+        }
+    }
+
+    fn hall() -> HallSlot {
+        HallSlot {
+            hall_id: 1,
+            capacity: 100,
+            busy: Vec::new(),
+        }
+    }
+
+    // Реклама 10 минут, фильм 90, уборка 15: зал занят 1 ч 55 мин
+    fn settings(pricing: PlanPricing) -> PlanSettings {
+        PlanSettings {
+            ad_minutes: 10,
+            cleaning_minutes: 15,
+            format: "2d".to_string(),
+            surcharge: Decimal::new(50, 0),
+            pricing,
+        }
+    }
+
+    fn plan(
+        halls: &mut [HallSlot],
+        films: &mut [CandidateFilm],
+        settings: &PlanSettings,
+    ) -> Vec<PlannedItem> {
+        let days = [(day(), at(10, 0), at(23, 0))];
+        plan_week(&days, halls, films, &HashMap::new(), settings)
+    }
+
+    fn starts(items: &[PlannedItem]) -> Vec<NaiveDateTime> {
+        items.iter().map(|item| item.start_time).collect()
+    }
+
+    #[test]
+    fn round_up_to_slot_rounds_to_next_step() {
+        assert_eq!(round_up_to_slot(at(10, 0)), at(10, 0));
+        assert_eq!(round_up_to_slot(at(10, 2)), at(10, 5));
+        assert_eq!(
+            round_up_to_slot(day().and_hms_opt(10, 0, 30).unwrap()),
+            at(10, 5)
+        );
+        assert_eq!(
+            round_up_to_slot(at(23, 58)),
+            day().succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn plan_week_fills_hall_back_to_back_until_closing() {
+        let mut halls = [hall()];
+        let mut films = [film(1, 0.5, 0)];
+        let items = plan(
+            &mut halls,
+            &mut films,
+            &settings(PlanPricing::Fixed(Decimal::new(200, 0))),
+        );
+
+        assert_eq!(
+            starts(&items),
+            vec![
+                at(10, 0),
+                at(11, 55),
+                at(13, 50),
+                at(15, 45),
+                at(17, 40),
+                at(19, 35)
+            ]
+        );
+        assert_eq!(films[0].planned, 6);
+    }
+
+    #[test]
+    fn plan_week_adds_format_surcharge_to_expected_revenue() {
+        let mut halls = [hall()];
+        let mut films = [film(1, 0.5, 0)];
+        let items = plan(
+            &mut halls,
+            &mut films,
+            &settings(PlanPricing::Fixed(Decimal::new(200, 0))),
+        );
+
+        assert_eq!(items[0].expected_tickets, 50);
+        assert_eq!(items[0].expected_revenue, Decimal::new(12500, 0));
+    }
+
+    #[test]
+    fn plan_week_meets_contract_minimum_of_each_film() {
+        let mut halls = [hall()];
+        let mut films = [film(1, 0.9, 0), film(2, 0.1, 2), film(3, 0.1, 0)];
+        let items = plan(
+            &mut halls,
+            &mut films,
+            &settings(PlanPricing::Fixed(Decimal::new(200, 0))),
+        );
+
+        let film_ids: Vec<i32> = items.iter().map(|item| item.film_id).collect();
+        assert_eq!(film_ids, vec![2, 2, 1, 1, 1, 1]);
+        assert_eq!(films[1].planned, 2);
+        assert_eq!(films[2].planned, 0);
+    }
+
+    #[test]
+    fn plan_week_works_around_existing_sessions() {
+        let mut halls = [hall()];
+        halls[0].busy.push((at(12, 0), at(14, 0)));
+        let mut films = [film(1, 0.5, 0)];
+        let items = plan(
+            &mut halls,
+            &mut films,
+            &settings(PlanPricing::Fixed(Decimal::new(200, 0))),
+        );
+
+        assert_eq!(
+            starts(&items),
+            vec![at(10, 0), at(14, 0), at(15, 55), at(17, 50), at(19, 45)]
+        );
+    }
+
+    #[test]
+    fn plan_week_prices_sessions_by_rules_and_skips_unpriced_slots() {
+        let evening = PriceRuleResponse {
+            rule_id: 1,
+            cinema_id: 1,
+            name: "Evening".to_string(),
+            priority: 0,
+            valid_from: None,
+            valid_to: None,
+            weekdays: None,
+            time_from: NaiveTime::from_hms_opt(18, 0, 0),
+            time_to: NaiveTime::from_hms_opt(23, 0, 0),
+            premiere_weeks: None,
+            format: None,
+            seat_category: None,
+            min_occupancy: None,
+            action: "set".to_string(),
+            value: Decimal::new(300, 0),
+            currency: "RUB".to_string(),
+            is_active: true,
+        };
+        let mut halls = [hall()];
+        let mut films = [film(1, 0.5, 0)];
+        let items = plan(
+            &mut halls,
+            &mut films,
+            &settings(PlanPricing::Rules(vec![evening])),
+        );
+
+        assert_eq!(starts(&items), vec![at(18, 0), at(19, 55)]);
+        assert_eq!(items[0].expected_revenue, Decimal::new(17500, 0));
+    }
+}
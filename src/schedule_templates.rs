@@ -0,0 +1,394 @@
+use actix_web::{HttpResponse, web};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::errors::AppError;
+use crate::films::ensure_film_exists;
+use crate::halls::find_active_hall;
+use crate::money::{ensure_cinema_currency, validate_amount};
+use crate::sessions::{SessionAttributes, SessionDraft, schedule_session};
+
+// Ограничение на число сеансов, которые создаются из шаблона за один раз
+const MAX_GENERATED_SESSIONS: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleTemplateResponse {
+    pub template_id: i32,
+    pub cinema_id: i32,
+    pub film_id: i32,
+    pub hall_id: Option<i32>,
+    pub ticket_price: Decimal,
+    pub currency: String,
+    pub weekdays: Vec<i32>,    // 1 — понедельник, 7 — воскресенье
+    pub times: Vec<NaiveTime>, // местное время кинотеатра
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub format: String,
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateScheduleTemplateRequest {
+    pub cinema_id: i32,
+    pub film_id: i32,
+    pub hall_id: Option<i32>,
+    pub ticket_price: Decimal,
+    pub currency: String, // должна совпадать с валютой кинотеатра
+    pub weekdays: Vec<i32>,
+    pub times: Vec<String>, // Формат "HH:MM:SS"
+    pub start_date: String, // Формат "YYYY-MM-DD"
+    pub end_date: String,   // Формат "YYYY-MM-DD"
+    #[serde(flatten)]
+    pub attributes: SessionAttributes,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleTemplateFilter {
+    pub cinema_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GenerateScheduleQuery {
+    pub dry_run: Option<bool>,
+}
+
+// Один сеанс из шаблона: созданный (или, при dry_run, тот, что был бы создан)
+// либо не прошедший проверки с объяснением причины
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeneratedSession {
+    pub start_time: String, // "YYYY-MM-DD HH:MM:SS", местное время кинотеатра
+    pub end_time: Option<String>,
+    pub session_id: Option<i32>, // None при dry_run и для конфликтов
+    pub problem: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GenerateScheduleResponse {
+    pub template_id: i32,
+    pub dry_run: bool,
+    pub created: usize, // при dry_run — сколько было бы создано
+    pub conflicts: usize,
+    pub sessions: Vec<GeneratedSession>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", value)))
+}
+
+fn parse_time(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid time {}, expected HH:MM:SS", value)))
+}
+
+// Все даты и время начала сеансов шаблона по порядку
+fn occurrences(template: &ScheduleTemplateResponse) -> Vec<NaiveDateTime> {
+    let mut times = template.times.clone();
+    times.sort();
+
+    let mut starts = Vec::new();
+    let mut date = template.start_date;
+    while date <= template.end_date {
+        let weekday = date.weekday().number_from_monday() as i32;
+        if template.weekdays.contains(&weekday) {
+            starts.extend(times.iter().map(|time| date.and_time(*time)));
+        }
+        date += Duration::days(1);
+    }
+
+    starts
+}
+
+async fn find_template(
+    pool: &PgPool,
+    template_id: i32,
+) -> Result<ScheduleTemplateResponse, AppError> {
+    sqlx::query_as!(
+        ScheduleTemplateResponse,
+        r#"
+        SELECT
+            template_id,
+            cinema_id,
+            film_id,
+            hall_id,
+            ticket_price,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = schedule_template.cinema_id
+            ) as "currency!",
+            weekdays,
+            times,
+            start_date,
+            end_date,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description
+        FROM schedule_template
+        WHERE template_id = $1
+        "#,
+        template_id
+    )
+    .fetch_optional(pool)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Schedule template not found".into()))
+}
+
+pub async fn get_schedule_templates(
+    pool: web::Data<PgPool>,
+    filter: web::Query<ScheduleTemplateFilter>,
+) -> Result<HttpResponse, AppError> {
+    let templates = sqlx::query_as!(
+        ScheduleTemplateResponse,
+        r#"
+        SELECT
+            template_id,
+            cinema_id,
+            film_id,
+            hall_id,
+            ticket_price,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = schedule_template.cinema_id
+            ) as "currency!",
+            weekdays,
+            times,
+            start_date,
+            end_date,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description
+        FROM schedule_template
+        WHERE ($1::int IS NULL OR cinema_id = $1)
+        ORDER BY start_date, template_id
+        "#,
+        filter.cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(templates))
+}
+
+pub async fn get_schedule_template(
+    pool: web::Data<PgPool>,
+    template_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let template = find_template(pool.get_ref(), template_id.into_inner()).await?;
+
+    Ok(HttpResponse::Ok().json(template))
+}
+
+pub async fn create_schedule_template(
+    pool: web::Data<PgPool>,
+    new_template: web::Json<CreateScheduleTemplateRequest>,
+) -> Result<HttpResponse, AppError> {
+    let start_date = parse_date(&new_template.start_date)?;
+    let end_date = parse_date(&new_template.end_date)?;
+    let times = new_template
+        .times
+        .iter()
+        .map(|t| parse_time(t))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if end_date < start_date {
+        return Err(AppError::InvalidInput(
+            "End date must not be before start date".into(),
+        ));
+    }
+    if new_template.weekdays.is_empty() || times.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Template needs at least one weekday and one start time".into(),
+        ));
+    }
+    if new_template.weekdays.iter().any(|d| !(1..=7).contains(d)) {
+        return Err(AppError::InvalidInput(
+            "Weekday must be between 1 (Monday) and 7 (Sunday)".into(),
+        ));
+    }
+    validate_amount("Ticket price", new_template.ticket_price)?;
+
+    new_template.attributes.validate()?;
+
+    ensure_cinema_currency(
+        pool.get_ref(),
+        new_template.cinema_id,
+        &new_template.currency,
+    )
+    .await?;
+    ensure_film_exists(pool.get_ref(), new_template.film_id).await?;
+    if let Some(hall_id) = new_template.hall_id {
+        find_active_hall(pool.get_ref(), new_template.cinema_id, hall_id).await?;
+    }
+
+    let template = sqlx::query_as!(
+        ScheduleTemplateResponse,
+        r#"
+        INSERT INTO schedule_template (
+            cinema_id,
+            film_id,
+            hall_id,
+            ticket_price,
+            weekdays,
+            times,
+            start_date,
+            end_date,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        RETURNING
+            template_id,
+            cinema_id,
+            film_id,
+            hall_id,
+            ticket_price,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = schedule_template.cinema_id
+            ) as "currency!",
+            weekdays,
+            times,
+            start_date,
+            end_date,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description
+        "#,
+        new_template.cinema_id,
+        new_template.film_id,
+        new_template.hall_id,
+        new_template.ticket_price,
+        &new_template.weekdays,
+        &times,
+        start_date,
+        end_date,
+        new_template.attributes.format,
+        new_template.attributes.audio_language,
+        new_template.attributes.subtitle_language,
+        new_template.attributes.audio_description
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Created().json(template))
+}
+
+pub async fn delete_schedule_template(
+    pool: web::Data<PgPool>,
+    template_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let result = sqlx::query!(
+        "DELETE FROM schedule_template WHERE template_id = $1",
+        template_id.into_inner()
+    )
+    .execute(pool.get_ref())
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Schedule template not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Создаёт все сеансы шаблона в одной транзакции: либо все, либо ни одного.
+// Каждый сеанс проходит те же проверки, что и при ручном создании.
+// При dry_run те же проверки и вставки выполняются, но транзакция
+// откатывается, а в ответе — что было бы создано и какие есть конфликты
+pub async fn generate_schedule(
+    pool: web::Data<PgPool>,
+    template_id: web::Path<i32>,
+    query: web::Query<GenerateScheduleQuery>,
+) -> Result<HttpResponse, AppError> {
+    let template_id = template_id.into_inner();
+    let dry_run = query.dry_run.unwrap_or(false);
+
+    let template = find_template(pool.get_ref(), template_id).await?;
+    let starts = occurrences(&template);
+    let attributes = SessionAttributes {
+        format: template.format.clone(),
+        audio_language: template.audio_language.clone(),
+        subtitle_language: template.subtitle_language.clone(),
+        audio_description: template.audio_description,
+    };
+
+    if starts.len() > MAX_GENERATED_SESSIONS {
+        return Err(AppError::InvalidInput(format!(
+            "Template produces {} sessions, at most {} can be generated at once",
+            starts.len(),
+            MAX_GENERATED_SESSIONS
+        )));
+    }
+
+    let mut tx = pool.begin().await?;
+    let mut sessions = Vec::with_capacity(starts.len());
+
+    for start_time in starts {
+        let draft = SessionDraft {
+            film_id: template.film_id,
+            cinema_id: template.cinema_id,
+            hall_id: template.hall_id,
+            start_time,
+            ticket_price: Some(template.ticket_price),
+            attributes: attributes.clone(),
+        };
+        let scheduled = schedule_session(&mut tx, &draft).await;
+
+        let start_time = start_time.format("%Y-%m-%d %H:%M:%S").to_string();
+        let generated = match scheduled {
+            Ok(session) => GeneratedSession {
+                start_time,
+                end_time: Some(session.end_time),
+                session_id: (!dry_run).then_some(session.session_id),
+                problem: None,
+            },
+            Err(AppError::InvalidInput(problem) | AppError::Conflict(problem)) => {
+                GeneratedSession {
+                    start_time,
+                    end_time: None,
+                    session_id: None,
+                    problem: Some(problem),
+                }
+            }
+            Err(err) => return Err(err),
+        };
+        sessions.push(generated);
+    }
+
+    let conflicts = sessions.iter().filter(|s| s.problem.is_some()).count();
+
+    if dry_run {
+        tx.rollback().await?;
+    } else if let Some(first) = sessions.iter().find(|s| s.problem.is_some()) {
+        tx.rollback().await?;
+        return Err(AppError::Conflict(format!(
+            "{} of {} sessions cannot be created, first at {}: {}",
+            conflicts,
+            sessions.len(),
+            first.start_time,
+            first.problem.as_deref().unwrap_or_default()
+        )));
+    } else {
+        tx.commit().await?;
+    }
+
+    let response = GenerateScheduleResponse {
+        template_id,
+        dry_run,
+        created: sessions.len() - conflicts,
+        conflicts,
+        sessions,
+    };
+
+    if dry_run {
+        Ok(HttpResponse::Ok().json(response))
+    } else {
+        Ok(HttpResponse::Created().json(response))
+    }
+}
@@ -0,0 +1,495 @@
+use actix_web::{HttpResponse, web};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgExecutor, PgPool};
+
+use crate::errors::AppError;
+use crate::halls::ensure_hall_exists;
+use crate::sessions::STATUS_SCHEDULED;
+
+pub const SEAT_CATEGORIES: [&str; 4] = ["standard", "vip", "sofa", "wheelchair"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SeatRecord {
+    pub seat_id: i32,
+    pub row_number: i32,
+    pub seat_number: i32,
+    pub column_index: i32,
+    pub category: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatCell {
+    pub seat_id: i32,
+    pub seat_number: i32,
+    pub category: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatRowResponse {
+    pub row_number: i32,
+    pub cells: Vec<Option<SeatCell>>, // null — проход
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeatMapResponse {
+    pub hall_id: i32,
+    pub columns: i32,
+    pub seat_count: i32,
+    pub rows: Vec<SeatRowResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AssignedSeat {
+    pub seat_id: i32,
+    pub row_number: i32,
+    pub seat_number: i32,
+    pub category: String,
+}
+
+// Ответ по бронированию или продаже вместе с закреплёнными местами
+#[derive(Debug, Serialize)]
+pub struct WithSeats<T> {
+    #[serde(flatten)]
+    pub inner: T,
+    pub seats: Vec<AssignedSeat>,
+}
+
+// Кому принадлежит место на сеансе: бронированию, продаже или временному удержанию
+#[derive(Debug, Clone, Copy)]
+pub enum SeatHolder {
+    Booking(i32),
+    Sale(i32),
+    Hold(i32),
+}
+
+impl SeatHolder {
+    fn ids(self) -> (Option<i32>, Option<i32>, Option<i32>) {
+        match self {
+            SeatHolder::Booking(id) => (Some(id), None, None),
+            SeatHolder::Sale(id) => (None, Some(id), None),
+            SeatHolder::Hold(id) => (None, None, Some(id)),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatRowRequest {
+    pub row_number: i32,
+    pub cells: Vec<Option<String>>, // категория места или null для прохода
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SeatMapRequest {
+    pub rows: Vec<SeatRowRequest>,
+}
+
+pub async fn fetch_seats(pool: &PgPool, hall_id: i32) -> Result<Vec<SeatRecord>, AppError> {
+    let seats = sqlx::query_as!(
+        SeatRecord,
+        r#"
+        SELECT
+            seat_id,
+            row_number,
+            seat_number,
+            column_index,
+            category
+        FROM seat
+        WHERE hall_id = $1 AND is_active
+        ORDER BY row_number, column_index
+        "#,
+        hall_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(seats)
+}
+
+async fn ensure_seat_map_unused(conn: &mut PgConnection, hall_id: i32) -> Result<(), AppError> {
+    // Продажа, бронь и удержание блокируют строку сеанса перед тем, как занять
+    // места, поэтому после этой блокировки новые места появиться не могут
+    sqlx::query!(
+        r#"
+        SELECT session_id
+        FROM session
+        WHERE hall_id = $1 AND status = $2 AND start_time > NOW()
+        FOR UPDATE
+        "#,
+        hall_id,
+        STATUS_SCHEDULED
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let in_use = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM session_seat ss
+            JOIN session s ON ss.session_id = s.session_id
+            LEFT JOIN seat_hold sh ON ss.hold_id = sh.hold_id
+            WHERE s.hall_id = $1
+            AND s.status = $2
+            AND s.start_time > NOW()
+            AND (ss.hold_id IS NULL OR sh.expires_at > NOW())
+        ) as "exists!"
+        "#,
+        hall_id,
+        STATUS_SCHEDULED
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if in_use {
+        return Err(AppError::Conflict(
+            "Seats of this hall are already booked or sold for upcoming sessions".into(),
+        ));
+    }
+
+    Ok(())
+}
+
+// Снимает текущую схему зала. Места, на которые уже были продажи или брони
+// прошедших сеансов, остаются в истории неактивными, остальные удаляются
+async fn retire_seats(conn: &mut PgConnection, hall_id: i32) -> Result<(), AppError> {
+    sqlx::query!(
+        r#"
+        UPDATE seat
+        SET is_active = FALSE
+        WHERE hall_id = $1
+        AND is_active
+        AND seat_id IN (SELECT seat_id FROM session_seat)
+        "#,
+        hall_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!("DELETE FROM seat WHERE hall_id = $1 AND is_active", hall_id)
+        .execute(&mut *conn)
+        .await?;
+
+    Ok(())
+}
+
+// Определяет число билетов для сеанса: в зале со схемой это число выбранных мест,
+// на сеансах без схемы (свободная рассадка) — переданный ticket_count
+pub async fn resolve_ticket_count(
+    conn: &mut PgConnection,
+    session_id: i32,
+    ticket_count: Option<i32>,
+    seat_ids: &[i32],
+) -> Result<i32, AppError> {
+    let has_seat_map = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM seat st WHERE st.hall_id = s.hall_id AND st.is_active
+        ) as "exists!"
+        FROM session s
+        WHERE s.session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    if !has_seat_map {
+        if !seat_ids.is_empty() {
+            return Err(AppError::InvalidInput(
+                "Session has general admission, seats cannot be chosen".into(),
+            ));
+        }
+        return match ticket_count {
+            Some(count) if count > 0 => Ok(count),
+            _ => Err(AppError::InvalidInput(
+                "Ticket count must be positive".into(),
+            )),
+        };
+    }
+
+    if seat_ids.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Session has assigned seating, seat_ids are required".into(),
+        ));
+    }
+    let mut unique = seat_ids.to_vec();
+    unique.sort_unstable();
+    unique.dedup();
+    if unique.len() != seat_ids.len() {
+        return Err(AppError::InvalidInput("Seat is listed twice".into()));
+    }
+    if let Some(count) = ticket_count
+        && count != seat_ids.len() as i32
+    {
+        return Err(AppError::InvalidInput(
+            "Ticket count does not match the number of seats".into(),
+        ));
+    }
+
+    Ok(seat_ids.len() as i32)
+}
+
+// Закрепляет места сеанса за бронированием, продажей или удержанием.
+// Первичный ключ (session_id, seat_id) не даёт занять место дважды
+// даже при параллельных запросах: второй INSERT дождётся первой транзакции
+// и ничего не вставит
+pub async fn assign_seats(
+    conn: &mut PgConnection,
+    session_id: i32,
+    seat_ids: &[i32],
+    holder: SeatHolder,
+) -> Result<Vec<AssignedSeat>, AppError> {
+    if seat_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let valid_count = sqlx::query_scalar!(
+        r#"
+        SELECT COUNT(*) as "count!"
+        FROM seat st
+        JOIN session s ON st.hall_id = s.hall_id
+        WHERE s.session_id = $1 AND st.seat_id = ANY($2) AND st.is_active
+        "#,
+        session_id,
+        seat_ids
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if valid_count != seat_ids.len() as i64 {
+        return Err(AppError::InvalidInput(
+            "Some seats do not belong to the session's hall".into(),
+        ));
+    }
+
+    // Истёкшие удержания освобождают места сразу, не дожидаясь фоновой очистки
+    sqlx::query!(
+        "DELETE FROM seat_hold WHERE session_id = $1 AND expires_at <= NOW()",
+        session_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    let (booking_id, sale_id, hold_id) = holder.ids();
+    let taken: Vec<i32> = sqlx::query_scalar!(
+        r#"
+        INSERT INTO session_seat (session_id, seat_id, booking_id, sale_id, hold_id)
+        SELECT $1, seat_id, $3, $4, $5 FROM UNNEST($2::int[]) as seat_id
+        ON CONFLICT (session_id, seat_id) DO NOTHING
+        RETURNING seat_id
+        "#,
+        session_id,
+        seat_ids,
+        booking_id,
+        sale_id,
+        hold_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    if taken.len() != seat_ids.len() {
+        let unavailable: Vec<i32> = seat_ids
+            .iter()
+            .copied()
+            .filter(|id| !taken.contains(id))
+            .collect();
+        return Err(AppError::Conflict(format!(
+            "Seats {:?} are already taken",
+            unavailable
+        )));
+    }
+
+    fetch_assigned_seats(&mut *conn, holder).await
+}
+
+pub async fn fetch_assigned_seats<'e>(
+    executor: impl PgExecutor<'e>,
+    holder: SeatHolder,
+) -> Result<Vec<AssignedSeat>, AppError> {
+    let (booking_id, sale_id, hold_id) = holder.ids();
+
+    let seats = sqlx::query_as!(
+        AssignedSeat,
+        r#"
+        SELECT
+            st.seat_id,
+            st.row_number,
+            st.seat_number,
+            st.category
+        FROM session_seat ss
+        JOIN seat st ON ss.seat_id = st.seat_id
+        WHERE ss.booking_id = $1 OR ss.sale_id = $2 OR ss.hold_id = $3
+        ORDER BY st.row_number, st.seat_number
+        "#,
+        booking_id,
+        sale_id,
+        hold_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(seats)
+}
+
+// Раскладывает места по сетке: каждая строка дополняется до общей ширины,
+// пустые клетки обозначают проходы
+pub fn build_grid<T>(
+    seats: &[SeatRecord],
+    cell: impl Fn(&SeatRecord) -> T,
+) -> (i32, Vec<(i32, Vec<Option<T>>)>) {
+    let columns = seats.iter().map(|s| s.column_index + 1).max().unwrap_or(0);
+    let mut rows: Vec<(i32, Vec<Option<T>>)> = Vec::new();
+
+    for seat in seats {
+        if rows.last().map(|(row, _)| *row) != Some(seat.row_number) {
+            rows.push((seat.row_number, (0..columns).map(|_| None).collect()));
+        }
+        if let Some((_, cells)) = rows.last_mut() {
+            cells[seat.column_index as usize] = Some(cell(seat));
+        }
+    }
+
+    (columns, rows)
+}
+
+fn to_response(hall_id: i32, seats: &[SeatRecord]) -> SeatMapResponse {
+    let (columns, rows) = build_grid(seats, |s| SeatCell {
+        seat_id: s.seat_id,
+        seat_number: s.seat_number,
+        category: s.category.clone(),
+    });
+
+    SeatMapResponse {
+        hall_id,
+        columns,
+        seat_count: seats.len() as i32,
+        rows: rows
+            .into_iter()
+            .map(|(row_number, cells)| SeatRowResponse { row_number, cells })
+            .collect(),
+    }
+}
+
+pub async fn get_seat_map(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+    ensure_hall_exists(pool.get_ref(), cinema_id, hall_id).await?;
+
+    let seats = fetch_seats(pool.get_ref(), hall_id).await?;
+
+    Ok(HttpResponse::Ok().json(to_response(hall_id, &seats)))
+}
+
+// Полностью заменяет схему зала; вместимость зала становится равной числу мест
+pub async fn update_seat_map(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    seat_map: web::Json<SeatMapRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+    ensure_hall_exists(pool.get_ref(), cinema_id, hall_id).await?;
+
+    let mut row_numbers = Vec::new();
+    let mut seat_numbers = Vec::new();
+    let mut column_indexes = Vec::new();
+    let mut categories = Vec::new();
+    let mut seen_rows = Vec::new();
+
+    for row in &seat_map.rows {
+        if row.row_number <= 0 {
+            return Err(AppError::InvalidInput(
+                "Row numbers must be positive".into(),
+            ));
+        }
+        if seen_rows.contains(&row.row_number) {
+            return Err(AppError::InvalidInput(format!(
+                "Row {} is defined twice",
+                row.row_number
+            )));
+        }
+        seen_rows.push(row.row_number);
+
+        let mut seat_number = 0;
+        for (column, cell) in row.cells.iter().enumerate() {
+            let Some(category) = cell else { continue };
+            if !SEAT_CATEGORIES.contains(&category.as_str()) {
+                return Err(AppError::InvalidInput(format!(
+                    "Unknown seat category {}, expected one of {:?}",
+                    category, SEAT_CATEGORIES
+                )));
+            }
+            seat_number += 1;
+            row_numbers.push(row.row_number);
+            seat_numbers.push(seat_number);
+            column_indexes.push(column as i32);
+            categories.push(category.clone());
+        }
+
+        if seat_number == 0 {
+            return Err(AppError::InvalidInput(format!(
+                "Row {} has no seats",
+                row.row_number
+            )));
+        }
+    }
+
+    if row_numbers.is_empty() {
+        return Err(AppError::InvalidInput(
+            "Seat map must contain at least one seat".into(),
+        ));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    ensure_seat_map_unused(&mut tx, hall_id).await?;
+    retire_seats(&mut tx, hall_id).await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO seat (hall_id, row_number, seat_number, column_index, category)
+        SELECT $1, * FROM UNNEST($2::int[], $3::int[], $4::int[], $5::text[])
+        "#,
+        hall_id,
+        &row_numbers,
+        &seat_numbers,
+        &column_indexes,
+        &categories
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE hall SET capacity = $1 WHERE hall_id = $2",
+        row_numbers.len() as i32,
+        hall_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let seats = fetch_seats(pool.get_ref(), hall_id).await?;
+
+    Ok(HttpResponse::Ok().json(to_response(hall_id, &seats)))
+}
+
+// Удаляет схему: зал снова продаётся без мест, по вместимости
+pub async fn delete_seat_map(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, hall_id) = path.into_inner();
+    ensure_hall_exists(pool.get_ref(), cinema_id, hall_id).await?;
+
+    let mut tx = pool.begin().await?;
+
+    ensure_seat_map_unused(&mut tx, hall_id).await?;
+    retire_seats(&mut tx, hall_id).await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,17 +1,113 @@
 use actix_web::{HttpResponse, web};
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgPool};
 
+use crate::availability::fetch_occupancy;
 use crate::errors::AppError;
+use crate::halls::{find_active_hall, supports_format};
+use crate::money::{ensure_cinema_currency, validate_amount};
+use crate::opening_hours;
+use crate::pricing::{PRICING_DYNAMIC, PRICING_FIXED, dynamic_ticket_price};
+
+pub const STATUS_SCHEDULED: &str = "scheduled";
+pub const STATUS_CANCELLED: &str = "cancelled";
+
+const REFUND_REASON_SESSION_CANCELLED: &str = "session_cancelled";
+
+pub const SESSION_FORMATS: [&str; 4] = ["2d", "3d", "imax", "4dx"];
+const DEFAULT_FORMAT: &str = "2d";
+const DEFAULT_AUDIO_LANGUAGE: &str = "ru";
+
+// Формат показа и звуковая дорожка. Языки — коды ISO 639-1 ("ru", "en")
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionAttributes {
+    #[serde(default = "default_format")]
+    pub format: String, // "2d", "3d", "imax", "4dx"
+    #[serde(default = "default_audio_language")]
+    pub audio_language: String,
+    #[serde(default)]
+    pub subtitle_language: Option<String>, // None — без субтитров
+    #[serde(default)]
+    pub audio_description: bool, // тифлокомментарий для незрячих зрителей
+}
+
+fn default_format() -> String {
+    DEFAULT_FORMAT.to_string()
+}
+
+fn default_audio_language() -> String {
+    DEFAULT_AUDIO_LANGUAGE.to_string()
+}
+
+impl Default for SessionAttributes {
+    fn default() -> Self {
+        SessionAttributes {
+            format: default_format(),
+            audio_language: default_audio_language(),
+            subtitle_language: None,
+            audio_description: false,
+        }
+    }
+}
+
+fn is_language_code(value: &str) -> bool {
+    (2..=3).contains(&value.len()) && value.chars().all(|c| c.is_ascii_lowercase())
+}
+
+impl SessionAttributes {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !SESSION_FORMATS.contains(&self.format.as_str()) {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown format {}, expected one of {:?}",
+                self.format, SESSION_FORMATS
+            )));
+        }
+        if !is_language_code(&self.audio_language)
+            || self
+                .subtitle_language
+                .as_deref()
+                .is_some_and(|lang| !is_language_code(lang))
+        {
+            return Err(AppError::InvalidInput(
+                "Languages must be lowercase ISO 639 codes, e.g. \"ru\" or \"en\"".into(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+// Всё, из чего создаётся сеанс; время — местное время кинотеатра
+#[derive(Debug, Clone)]
+pub struct SessionDraft {
+    pub film_id: i32,
+    pub cinema_id: i32,
+    pub hall_id: Option<i32>,
+    pub start_time: NaiveDateTime,
+    pub ticket_price: Option<Decimal>, // в валюте кинотеатра; None — цену дают правила
+    pub attributes: SessionAttributes,
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SessionResponse {
     pub session_id: i32,
     pub film_id: i32,
     pub cinema_id: i32,
-    pub start_time: String,
-    pub ticket_price: f64,
+    pub hall_id: Option<i32>,
+    pub start_time: String, // местное время кинотеатра со смещением, "2025-03-01T19:30:00+05:00"
+    pub end_time: String,   // начало + реклама + хронометраж фильма
+    pub format: String,
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+    pub pricing_mode: String, // "fixed" — цена введена вручную, "dynamic" — цену дают правила
+    pub ticket_price: Decimal, // у динамических сеансов — цена места без категории в пустом зале
+    pub surcharge: Decimal,   // доплата кинотеатра за формат
+    pub price: Decimal,       // ticket_price + surcharge
+    pub currency: String,
+    pub status: String, // "scheduled", "cancelled"
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -19,31 +115,454 @@ pub struct SessionWithFilmResponse {
     pub session_id: i32,
     pub film_title: String,
     pub cinema_name: String,
+    pub hall_name: Option<String>,
     pub start_time: String,
-    pub ticket_price: f64,
+    pub end_time: String,
+    pub format: String,
+    pub audio_language: String,
+    pub subtitle_language: Option<String>,
+    pub audio_description: bool,
+    pub pricing_mode: String,
+    pub ticket_price: Decimal,
+    pub surcharge: Decimal,
+    pub price: Decimal,
+    pub currency: String,
     pub age_restriction: String,
+    pub status: String,
+    pub seats_free: Option<i32>, // None — зал не задан, вместимость неизвестна
 }
 
 #[derive(Debug, Deserialize)]
 pub struct CreateSessionRequest {
     pub film_id: i32,
     pub cinema_id: i32,
-    pub start_time: String,
-    pub ticket_price: f64,
+    pub hall_id: Option<i32>,
+    pub start_time: String, // "YYYY-MM-DD HH:MM:SS", местное время кинотеатра
+    pub ticket_price: Option<Decimal>, // без цены сеанс оценивается правилами кинотеатра
+    pub currency: String,   // должна совпадать с валютой кинотеатра
+    #[serde(flatten)]
+    pub attributes: SessionAttributes,
+}
+
+// Фильтры списков сеансов. Даты и время суток — местные для кинотеатра.
+// date принимает также "today" и "tomorrow"
+#[derive(Debug, Deserialize)]
+pub struct SessionFilter {
+    pub cinema_id: Option<i32>,
+    pub film_id: Option<i32>,
+    pub hall_id: Option<i32>,
+    pub date: Option<String>,      // "YYYY-MM-DD", "today", "tomorrow"
+    pub from: Option<String>,      // Формат "YYYY-MM-DD", включительно
+    pub to: Option<String>,        // Формат "YYYY-MM-DD", включительно
+    pub time_from: Option<String>, // Формат "HH:MM:SS", включительно
+    pub time_to: Option<String>,   // Формат "HH:MM:SS", не включительно
+    pub format: Option<String>,    // "2d", "3d", "imax", "4dx"
+    pub language: Option<String>,  // язык звуковой дорожки
+    pub subtitles: Option<String>, // язык субтитров или "none"
+    pub audio_description: Option<bool>,
+}
+
+// Разобранные фильтры. День относительно сегодняшнего считается в SQL,
+// потому что "сегодня" у кинотеатров в разных часовых поясах разное
+#[derive(Debug, Default)]
+struct ParsedSessionFilter {
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    day_offset: Option<i32>,
+    time_from: Option<NaiveTime>,
+    time_to: Option<NaiveTime>,
 }
 
-pub async fn get_sessions(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+#[derive(Debug, Deserialize)]
+pub struct UpdateSessionRequest {
+    pub hall_id: Option<i32>,
+    pub start_time: String, // "YYYY-MM-DD HH:MM:SS", местное время кинотеатра
+    pub ticket_price: Option<Decimal>, // без цены сеанс оценивается правилами кинотеатра
+    pub currency: String,   // должна совпадать с валютой кинотеатра
+    #[serde(flatten)]
+    pub attributes: SessionAttributes,
+}
+
+// Активная бронь, владельца которой нужно предупредить об изменении сеанса
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AffectedBookingResponse {
+    pub booking_id: i32,
+    pub customer_id: i32,
+    pub customer_name: String,
+    pub phone: String,
+    pub ticket_count: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionUpdateResponse {
+    pub session: SessionResponse,
+    pub changed: Vec<String>, // "start_time", "hall_id", "ticket_price", "attributes"
+    pub affected_bookings: Vec<AffectedBookingResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefundResponse {
+    pub refund_id: i32,
+    pub sale_id: i32,
+    pub customer_id: i32,
+    pub amount: Decimal,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionCancellationResponse {
+    pub session_id: i32,
+    pub cancelled_bookings: Vec<i32>,
+    pub refunds: Vec<RefundResponse>,
+    pub total_refunded: Decimal,
+    pub currency: String,
+}
+
+fn parse_filter_date(value: &str) -> Result<NaiveDate, AppError> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid date {}, expected YYYY-MM-DD", value)))
+}
+
+fn parse_filter_time(value: &str) -> Result<NaiveTime, AppError> {
+    NaiveTime::parse_from_str(value, "%H:%M:%S")
+        .map_err(|_| AppError::InvalidInput(format!("Invalid time {}, expected HH:MM:SS", value)))
+}
+
+fn parse_session_filter(filter: &SessionFilter) -> Result<ParsedSessionFilter, AppError> {
+    let mut parsed = ParsedSessionFilter::default();
+
+    if let Some(date) = &filter.date {
+        if filter.from.is_some() || filter.to.is_some() {
+            return Err(AppError::InvalidInput(
+                "Use either 'date' or 'from'/'to', not both".into(),
+            ));
+        }
+        match date.as_str() {
+            "today" => parsed.day_offset = Some(0),
+            "tomorrow" => parsed.day_offset = Some(1),
+            date => {
+                let date = parse_filter_date(date)?;
+                parsed.from = Some(date);
+                parsed.to = Some(date);
+            }
+        }
+    } else {
+        parsed.from = filter.from.as_deref().map(parse_filter_date).transpose()?;
+        parsed.to = filter.to.as_deref().map(parse_filter_date).transpose()?;
+    }
+
+    if let (Some(from), Some(to)) = (parsed.from, parsed.to)
+        && to < from
+    {
+        return Err(AppError::InvalidInput(
+            "'to' must not be before 'from'".into(),
+        ));
+    }
+
+    parsed.time_from = filter
+        .time_from
+        .as_deref()
+        .map(parse_filter_time)
+        .transpose()?;
+    parsed.time_to = filter
+        .time_to
+        .as_deref()
+        .map(parse_filter_time)
+        .transpose()?;
+
+    if let Some(format) = &filter.format
+        && !SESSION_FORMATS.contains(&format.as_str())
+    {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown format {}, expected one of {:?}",
+            format, SESSION_FORMATS
+        )));
+    }
+
+    Ok(parsed)
+}
+
+// Сеанс можно поставить только в прокатный период фильма, с известным
+// хронометражем и только если кинотеатр заказал копию с доставкой не позднее дня показа
+async fn ensure_film_can_be_screened(
+    conn: &mut PgConnection,
+    film_id: i32,
+    cinema_id: i32,
+    start_time: NaiveDateTime,
+) -> Result<(), AppError> {
+    let film = sqlx::query!(
+        "SELECT start_date, end_date, duration_estimated FROM film WHERE film_id = $1",
+        film_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Film not found".into()))?;
+
+    if film.duration_estimated {
+        return Err(AppError::InvalidInput(
+            "Film duration is only estimated, set duration_minutes before scheduling sessions"
+                .into(),
+        ));
+    }
+
+    let session_date = start_time.date();
+    if session_date < film.start_date || session_date > film.end_date {
+        return Err(AppError::InvalidInput(format!(
+            "Film is only in release from {} to {}",
+            film.start_date, film.end_date
+        )));
+    }
+
+    let has_copy = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM film_order
+            WHERE film_id = $1
+            AND cinema_id = $2
+            AND delivery_date <= $3
+            AND status IN ('ordered', 'delivered')
+        ) as "exists!"
+        "#,
+        film_id,
+        cinema_id,
+        session_date
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    if !has_copy {
+        return Err(AppError::InvalidInput(format!(
+            "Cinema has no copy of the film delivered by {}",
+            session_date
+        )));
+    }
+
+    Ok(())
+}
+
+// Сеанс должен закончиться до закрытия с учётом рекламы перед фильмом
+async fn ensure_within_opening_hours(
+    conn: &mut PgConnection,
+    film_id: i32,
+    cinema_id: i32,
+    start_time: NaiveDateTime,
+) -> Result<(), AppError> {
+    let minutes = sqlx::query_scalar!(
+        r#"
+        SELECT c.ad_minutes + f.duration_minutes as "minutes!"
+        FROM cinema c, film f
+        WHERE c.cinema_id = $1 AND f.film_id = $2
+        "#,
+        cinema_id,
+        film_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Film or cinema not found".into()))?;
+
+    let end_time = start_time + Duration::minutes(minutes as i64);
+
+    opening_hours::ensure_within_opening_hours(conn, cinema_id, start_time, end_time).await
+}
+
+// Сеансы в одном зале не должны пересекаться. Зал занят от начала сеанса
+// на время рекламы, самого фильма и уборки после него.
+// Строка зала блокируется до конца транзакции, чтобы параллельно
+// созданные сеансы не прошли проверку одновременно.
+// start_time — местное время кинотеатра, в момент времени его переводит Postgres
+async fn ensure_hall_is_free(
+    conn: &mut PgConnection,
+    hall_id: i32,
+    film_id: i32,
+    cinema_id: i32,
+    start_time: NaiveDateTime,
+    exclude_session_id: Option<i32>,
+) -> Result<(), AppError> {
+    sqlx::query!(
+        "SELECT hall_id FROM hall WHERE hall_id = $1 FOR UPDATE",
+        hall_id
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let occupied_minutes = sqlx::query_scalar!(
+        r#"
+        SELECT c.ad_minutes + f.duration_minutes + c.cleaning_minutes as "minutes!"
+        FROM film f, cinema c
+        WHERE f.film_id = $1 AND c.cinema_id = $2
+        "#,
+        film_id,
+        cinema_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Film or cinema not found".into()))?;
+
+    let busy_until = start_time + Duration::minutes(occupied_minutes as i64);
+
+    let conflict = sqlx::query!(
+        r#"
+        SELECT
+            s.session_id,
+            f.title,
+            to_local_iso(s.start_time, c.timezone) as "start_time!"
+        FROM session s
+        JOIN film f ON s.film_id = f.film_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE s.hall_id = $1
+        AND ($4::int IS NULL OR s.session_id <> $4)
+        AND s.status <> 'cancelled'
+        AND s.start_time < ($3::timestamp AT TIME ZONE c.timezone)
+        AND s.start_time
+            + make_interval(mins => c.ad_minutes + f.duration_minutes + c.cleaning_minutes)
+            > ($2::timestamp AT TIME ZONE c.timezone)
+        ORDER BY s.start_time
+        LIMIT 1
+        "#,
+        hall_id,
+        start_time,
+        busy_until,
+        exclude_session_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    if let Some(conflict) = conflict {
+        return Err(AppError::Conflict(format!(
+            "Hall is busy: session {} ({} at {}) overlaps",
+            conflict.session_id, conflict.title, conflict.start_time
+        )));
+    }
+
+    Ok(())
+}
+
+// Будущие сеансы фильма после того, как его хронометраж вырос: каждый
+// по-прежнему должен укладываться в часы работы и не пересекаться
+// с соседями по залу. Вызывается в транзакции, где хронометраж уже изменён
+pub async fn ensure_film_sessions_fit(
+    conn: &mut PgConnection,
+    film_id: i32,
+) -> Result<(), AppError> {
+    let sessions = sqlx::query!(
+        r#"
+        SELECT
+            s.session_id,
+            s.cinema_id,
+            s.hall_id,
+            (s.start_time AT TIME ZONE c.timezone) as "start_time!"
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE s.film_id = $1
+        AND s.status = $2
+        AND s.start_time > NOW()
+        ORDER BY s.start_time
+        "#,
+        film_id,
+        STATUS_SCHEDULED
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for session in &sessions {
+        let no_longer_fits = |err| match err {
+            AppError::InvalidInput(problem) | AppError::Conflict(problem) => {
+                AppError::Conflict(format!(
+                    "Session {} no longer fits with the new duration: {}",
+                    session.session_id, problem
+                ))
+            }
+            err => err,
+        };
+
+        ensure_within_opening_hours(conn, film_id, session.cinema_id, session.start_time)
+            .await
+            .map_err(no_longer_fits)?;
+
+        if let Some(hall_id) = session.hall_id {
+            ensure_hall_is_free(
+                conn,
+                hall_id,
+                film_id,
+                session.cinema_id,
+                session.start_time,
+                Some(session.session_id),
+            )
+            .await
+            .map_err(no_longer_fits)?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn get_sessions(
+    pool: web::Data<PgPool>,
+    filter: web::Query<SessionFilter>,
+) -> Result<HttpResponse, AppError> {
+    let parsed = parse_session_filter(&filter)?;
+
     let sessions = sqlx::query_as!(
         SessionResponse,
         r#"
         SELECT
-            session_id,
-            film_id,
-            cinema_id,
-            start_time::text,
-            ticket_price
-        FROM session
-        "#
+            s.session_id,
+            s.film_id,
+            s.cinema_id,
+            s.hall_id,
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
+            to_local_iso(
+                s.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                c.timezone
+            ) as "end_time!",
+            s.format,
+            s.audio_language,
+            s.subtitle_language,
+            s.audio_description,
+            s.pricing_mode,
+            s.ticket_price,
+            session_surcharge(s.cinema_id, s.format) as "surcharge!",
+            s.ticket_price + session_surcharge(s.cinema_id, s.format) as "price!",
+            c.currency::text as "currency!",
+            s.status
+        FROM session s
+        JOIN film f ON s.film_id = f.film_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE ($1::int IS NULL OR s.cinema_id = $1)
+        AND ($2::int IS NULL OR s.film_id = $2)
+        AND ($3::int IS NULL OR s.hall_id = $3)
+        AND ($4::date IS NULL OR (s.start_time AT TIME ZONE c.timezone)::date >= $4)
+        AND ($5::date IS NULL OR (s.start_time AT TIME ZONE c.timezone)::date <= $5)
+        AND (
+            $6::int IS NULL
+            OR (s.start_time AT TIME ZONE c.timezone)::date
+                = (NOW() AT TIME ZONE c.timezone)::date + $6
+        )
+        AND ($7::time IS NULL OR (s.start_time AT TIME ZONE c.timezone)::time >= $7)
+        AND ($8::time IS NULL OR (s.start_time AT TIME ZONE c.timezone)::time < $8)
+        AND ($9::text IS NULL OR s.format = $9)
+        AND ($10::text IS NULL OR s.audio_language = $10)
+        AND (
+            $11::text IS NULL
+            OR ($11 = 'none' AND s.subtitle_language IS NULL)
+            OR s.subtitle_language = $11
+        )
+        AND ($12::bool IS NULL OR s.audio_description = $12)
+        ORDER BY s.start_time, s.session_id
+        "#,
+        filter.cinema_id,
+        filter.film_id,
+        filter.hall_id,
+        parsed.from,
+        parsed.to,
+        parsed.day_offset,
+        parsed.time_from,
+        parsed.time_to,
+        filter.format,
+        filter.language,
+        filter.subtitles,
+        filter.audio_description
     )
     .fetch_all(pool.get_ref())
     .await?;
@@ -51,7 +570,12 @@ pub async fn get_sessions(pool: web::Data<PgPool>) -> Result<HttpResponse, AppEr
     Ok(HttpResponse::Ok().json(sessions))
 }
 
-pub async fn get_upcoming_sessions(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+pub async fn get_upcoming_sessions(
+    pool: web::Data<PgPool>,
+    filter: web::Query<SessionFilter>,
+) -> Result<HttpResponse, AppError> {
+    let parsed = parse_session_filter(&filter)?;
+
     let sessions = sqlx::query_as!(
         SessionWithFilmResponse,
         r#"
@@ -59,13 +583,105 @@ pub async fn get_upcoming_sessions(pool: web::Data<PgPool>) -> Result<HttpRespon
             s.session_id,
             f.title as film_title,
             c.name as cinema_name,
-            s.start_time::text,
+            h.name as "hall_name?",
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
+            to_local_iso(
+                s.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                c.timezone
+            ) as "end_time!",
+            s.format,
+            s.audio_language,
+            s.subtitle_language,
+            s.audio_description,
+            s.pricing_mode,
             s.ticket_price,
-            f.age_restriction
+            session_surcharge(s.cinema_id, s.format) as "surcharge!",
+            s.ticket_price + session_surcharge(s.cinema_id, s.format) as "price!",
+            c.currency::text as "currency!",
+            f.age_restriction,
+            s.status,
+            session_seats_free(s.session_id) as "seats_free?"
         FROM session s
         JOIN film f ON s.film_id = f.film_id
         JOIN cinema c ON s.cinema_id = c.cinema_id
+        LEFT JOIN hall h ON s.hall_id = h.hall_id
         WHERE s.start_time > NOW()
+        AND s.status = 'scheduled'
+        AND ($1::int IS NULL OR s.cinema_id = $1)
+        AND ($2::int IS NULL OR s.film_id = $2)
+        AND ($3::int IS NULL OR s.hall_id = $3)
+        AND ($4::date IS NULL OR (s.start_time AT TIME ZONE c.timezone)::date >= $4)
+        AND ($5::date IS NULL OR (s.start_time AT TIME ZONE c.timezone)::date <= $5)
+        AND (
+            $6::int IS NULL
+            OR (s.start_time AT TIME ZONE c.timezone)::date
+                = (NOW() AT TIME ZONE c.timezone)::date + $6
+        )
+        AND ($7::time IS NULL OR (s.start_time AT TIME ZONE c.timezone)::time >= $7)
+        AND ($8::time IS NULL OR (s.start_time AT TIME ZONE c.timezone)::time < $8)
+        AND ($9::text IS NULL OR s.format = $9)
+        AND ($10::text IS NULL OR s.audio_language = $10)
+        AND (
+            $11::text IS NULL
+            OR ($11 = 'none' AND s.subtitle_language IS NULL)
+            OR s.subtitle_language = $11
+        )
+        AND ($12::bool IS NULL OR s.audio_description = $12)
+        ORDER BY s.start_time
+        "#,
+        filter.cinema_id,
+        filter.film_id,
+        filter.hall_id,
+        parsed.from,
+        parsed.to,
+        parsed.day_offset,
+        parsed.time_from,
+        parsed.time_to,
+        filter.format,
+        filter.language,
+        filter.subtitles,
+        filter.audio_description
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+// Сеансы, которые идут прямо сейчас
+pub async fn get_current_sessions(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
+    let sessions = sqlx::query_as!(
+        SessionWithFilmResponse,
+        r#"
+        SELECT
+            s.session_id,
+            f.title as film_title,
+            c.name as cinema_name,
+            h.name as "hall_name?",
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
+            to_local_iso(
+                s.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                c.timezone
+            ) as "end_time!",
+            s.format,
+            s.audio_language,
+            s.subtitle_language,
+            s.audio_description,
+            s.pricing_mode,
+            s.ticket_price,
+            session_surcharge(s.cinema_id, s.format) as "surcharge!",
+            s.ticket_price + session_surcharge(s.cinema_id, s.format) as "price!",
+            c.currency::text as "currency!",
+            f.age_restriction,
+            s.status,
+            session_seats_free(s.session_id) as "seats_free?"
+        FROM session s
+        JOIN film f ON s.film_id = f.film_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        LEFT JOIN hall h ON s.hall_id = h.hall_id
+        WHERE s.start_time <= NOW()
+        AND s.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes) > NOW()
+        AND s.status = 'scheduled'
         ORDER BY s.start_time
         "#
     )
@@ -86,12 +702,28 @@ pub async fn get_session(
             s.session_id,
             f.title as film_title,
             c.name as cinema_name,
-            s.start_time::text,
+            h.name as "hall_name?",
+            to_local_iso(s.start_time, c.timezone) as "start_time!",
+            to_local_iso(
+                s.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                c.timezone
+            ) as "end_time!",
+            s.format,
+            s.audio_language,
+            s.subtitle_language,
+            s.audio_description,
+            s.pricing_mode,
             s.ticket_price,
-            f.age_restriction
+            session_surcharge(s.cinema_id, s.format) as "surcharge!",
+            s.ticket_price + session_surcharge(s.cinema_id, s.format) as "price!",
+            c.currency::text as "currency!",
+            f.age_restriction,
+            s.status,
+            session_seats_free(s.session_id) as "seats_free?"
         FROM session s
         JOIN film f ON s.film_id = f.film_id
         JOIN cinema c ON s.cinema_id = c.cinema_id
+        LEFT JOIN hall h ON s.hall_id = h.hall_id
         WHERE s.session_id = $1
         "#,
         session_id.into_inner()
@@ -105,46 +737,564 @@ pub async fn get_session(
     }
 }
 
+fn ensure_hall_supports_format(
+    hall_name: &str,
+    screen_type: &str,
+    format: &str,
+) -> Result<(), AppError> {
+    if !supports_format(screen_type, format) {
+        return Err(AppError::InvalidInput(format!(
+            "Hall {} ({} screen) cannot show {} sessions",
+            hall_name, screen_type, format
+        )));
+    }
+
+    Ok(())
+}
+
+fn parse_start_time(value: &str) -> Result<NaiveDateTime, AppError> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").map_err(|_| {
+        AppError::InvalidInput(format!(
+            "Invalid start time {}, expected YYYY-MM-DD HH:MM:SS",
+            value
+        ))
+    })
+}
+
+// Цена, которую сохранить в сеансе: введённая вручную или, если её нет,
+// цена по правилам кинотеатра для места без категории в пустом зале
+async fn resolve_ticket_price(
+    conn: &mut PgConnection,
+    cinema_id: i32,
+    film_id: i32,
+    start_time: NaiveDateTime,
+    format: &str,
+    ticket_price: Option<Decimal>,
+) -> Result<(&'static str, Decimal), AppError> {
+    if let Some(ticket_price) = ticket_price {
+        return Ok((PRICING_FIXED, ticket_price));
+    }
+
+    let price = dynamic_ticket_price(conn, cinema_id, film_id, start_time, format)
+        .await?
+        .ok_or_else(|| {
+            AppError::InvalidInput(
+                "No active price rule sets a price for this session, give it a ticket_price".into(),
+            )
+        })?;
+
+    Ok((PRICING_DYNAMIC, price))
+}
+
+// Все проверки, которые проходит новый сеанс, и его вставка
+// в транзакции вызывающего. Проверки идут на том же соединении, второе
+// соединение из пула на время транзакции не занимается.
+// Сеансы, вставленные раньше в той же транзакции,
+// уже учитываются при проверке занятости зала
+pub async fn schedule_session(
+    conn: &mut PgConnection,
+    draft: &SessionDraft,
+) -> Result<SessionResponse, AppError> {
+    let SessionDraft {
+        film_id,
+        cinema_id,
+        hall_id,
+        start_time,
+        ticket_price,
+        ref attributes,
+    } = *draft;
+
+    if let Some(ticket_price) = ticket_price {
+        validate_amount("Ticket price", ticket_price)?;
+    }
+    attributes.validate()?;
+    ensure_film_can_be_screened(conn, film_id, cinema_id, start_time).await?;
+    ensure_within_opening_hours(conn, film_id, cinema_id, start_time).await?;
+
+    let (pricing_mode, ticket_price) = resolve_ticket_price(
+        conn,
+        cinema_id,
+        film_id,
+        start_time,
+        &attributes.format,
+        ticket_price,
+    )
+    .await?;
+
+    if let Some(hall_id) = hall_id {
+        let hall = find_active_hall(&mut *conn, cinema_id, hall_id).await?;
+        ensure_hall_supports_format(&hall.name, &hall.screen_type, &attributes.format)?;
+        ensure_hall_is_free(conn, hall_id, film_id, cinema_id, start_time, None).await?;
+    }
+
+    let session = sqlx::query_as!(
+        SessionResponse,
+        r#"
+        INSERT INTO session (
+            film_id,
+            cinema_id,
+            hall_id,
+            start_time,
+            pricing_mode,
+            ticket_price,
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description
+        )
+        VALUES (
+            $1,
+            $2,
+            $3,
+            $4::timestamp AT TIME ZONE (SELECT timezone FROM cinema WHERE cinema_id = $2),
+            $5,
+            $6,
+            $7,
+            $8,
+            $9,
+            $10
+        )
+        RETURNING
+            session_id,
+            film_id,
+            cinema_id,
+            hall_id,
+            (
+                SELECT to_local_iso(session.start_time, c.timezone)
+                FROM cinema c
+                WHERE c.cinema_id = session.cinema_id
+            ) as "start_time!",
+            (
+                SELECT to_local_iso(
+                    session.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                    c.timezone
+                )
+                FROM film f, cinema c
+                WHERE f.film_id = session.film_id AND c.cinema_id = session.cinema_id
+            ) as "end_time!",
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description,
+            pricing_mode,
+            ticket_price,
+            session_surcharge(cinema_id, format) as "surcharge!",
+            ticket_price + session_surcharge(cinema_id, format) as "price!",
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = session.cinema_id
+            ) as "currency!",
+            status
+        "#,
+        film_id,
+        cinema_id,
+        hall_id,
+        start_time,
+        pricing_mode,
+        ticket_price,
+        attributes.format,
+        attributes.audio_language,
+        attributes.subtitle_language,
+        attributes.audio_description
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(session)
+}
+
 pub async fn create_session(
     pool: web::Data<PgPool>,
     new_session: web::Json<CreateSessionRequest>,
 ) -> Result<HttpResponse, AppError> {
-    let start_time = NaiveDateTime::parse_from_str(&new_session.start_time, "%Y-%m-%d %H:%M:%S")?;
+    let start_time = parse_start_time(&new_session.start_time)?;
+    ensure_cinema_currency(pool.get_ref(), new_session.cinema_id, &new_session.currency).await?;
+
+    let mut tx = pool.begin().await?;
+
+    let session = schedule_session(
+        &mut tx,
+        &SessionDraft {
+            film_id: new_session.film_id,
+            cinema_id: new_session.cinema_id,
+            hall_id: new_session.hall_id,
+            start_time,
+            ticket_price: new_session.ticket_price,
+            attributes: new_session.attributes.clone(),
+        },
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(session))
+}
+
+// Перенос сеанса проверяется так же, как создание. Сменить зал можно,
+// только пока на сеанс не закреплены места, и новый зал должен вместить
+// уже проданные, забронированные и удержанные билеты.
+// Строка сеанса блокируется до чтения, поэтому параллельная отмена или
+// другое изменение не проскочат между проверками и записью
+pub async fn update_session(
+    pool: web::Data<PgPool>,
+    session_id: web::Path<i32>,
+    updated_session: web::Json<UpdateSessionRequest>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = session_id.into_inner();
+    let start_time = parse_start_time(&updated_session.start_time)?;
+    let attributes = &updated_session.attributes;
+    attributes.validate()?;
+    if let Some(ticket_price) = updated_session.ticket_price {
+        validate_amount("Ticket price", ticket_price)?;
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let current = sqlx::query!(
+        r#"
+        SELECT
+            s.film_id,
+            s.cinema_id,
+            s.hall_id,
+            (s.start_time AT TIME ZONE c.timezone) as "start_time!",
+            s.pricing_mode,
+            s.ticket_price,
+            s.format,
+            s.audio_language,
+            s.subtitle_language,
+            s.audio_description,
+            s.status,
+            c.currency::text as "currency!"
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE s.session_id = $1
+        FOR UPDATE OF s
+        "#,
+        session_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    if current.status == STATUS_CANCELLED {
+        return Err(AppError::Conflict(
+            "Cancelled session cannot be changed".into(),
+        ));
+    }
+    if current.currency != updated_session.currency {
+        return Err(AppError::InvalidInput(format!(
+            "Amounts for this cinema must be in {}, got {}",
+            current.currency, updated_session.currency
+        )));
+    }
+
+    ensure_film_can_be_screened(&mut tx, current.film_id, current.cinema_id, start_time).await?;
+
+    ensure_within_opening_hours(&mut tx, current.film_id, current.cinema_id, start_time).await?;
+
+    let mut changed = Vec::new();
+    if current.start_time != start_time {
+        changed.push("start_time".to_string());
+    }
+    if current.hall_id != updated_session.hall_id {
+        changed.push("hall_id".to_string());
+    }
+    let current_attributes = SessionAttributes {
+        format: current.format,
+        audio_language: current.audio_language,
+        subtitle_language: current.subtitle_language,
+        audio_description: current.audio_description,
+    };
+    if current_attributes != *attributes {
+        changed.push("attributes".to_string());
+    }
+
+    let (pricing_mode, ticket_price) = resolve_ticket_price(
+        &mut tx,
+        current.cinema_id,
+        current.film_id,
+        start_time,
+        &attributes.format,
+        updated_session.ticket_price,
+    )
+    .await?;
+    if current.ticket_price != ticket_price || current.pricing_mode != pricing_mode {
+        changed.push("ticket_price".to_string());
+    }
+
+    if current.hall_id != updated_session.hall_id {
+        let has_seats = sqlx::query_scalar!(
+            r#"
+            SELECT EXISTS(
+                SELECT 1
+                FROM session_seat ss
+                LEFT JOIN seat_hold sh ON ss.hold_id = sh.hold_id
+                WHERE ss.session_id = $1
+                AND (ss.hold_id IS NULL OR sh.expires_at > NOW())
+            ) as "exists!"
+            "#,
+            session_id
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if has_seats {
+            return Err(AppError::Conflict(
+                "Session has assigned seats and cannot be moved to another hall".into(),
+            ));
+        }
+    }
+
+    if let Some(hall_id) = updated_session.hall_id {
+        let hall = find_active_hall(&mut *tx, current.cinema_id, hall_id).await?;
+        ensure_hall_supports_format(&hall.name, &hall.screen_type, &attributes.format)?;
+
+        let occupancy = fetch_occupancy(&mut *tx, session_id).await?;
+        let taken = occupancy.sold + occupancy.booked + occupancy.held;
+        if taken > hall.capacity {
+            return Err(AppError::Conflict(format!(
+                "Hall {} has {} seats, but {} tickets are already taken",
+                hall.name, hall.capacity, taken
+            )));
+        }
+
+        ensure_hall_is_free(
+            &mut tx,
+            hall_id,
+            current.film_id,
+            current.cinema_id,
+            start_time,
+            Some(session_id),
+        )
+        .await?;
+    }
 
     let session = sqlx::query_as!(
         SessionResponse,
         r#"
-        INSERT INTO session (film_id, cinema_id, start_time, ticket_price)
-        VALUES ($1, $2, $3, $4)
+        UPDATE session
+        SET
+            hall_id = $1,
+            start_time = $2::timestamp AT TIME ZONE (
+                SELECT timezone FROM cinema WHERE cinema_id = session.cinema_id
+            ),
+            pricing_mode = $3,
+            ticket_price = $4,
+            format = $5,
+            audio_language = $6,
+            subtitle_language = $7,
+            audio_description = $8
+        WHERE session_id = $9
         RETURNING
             session_id,
             film_id,
             cinema_id,
-            start_time::text,
-            ticket_price
+            hall_id,
+            (
+                SELECT to_local_iso(session.start_time, c.timezone)
+                FROM cinema c
+                WHERE c.cinema_id = session.cinema_id
+            ) as "start_time!",
+            (
+                SELECT to_local_iso(
+                    session.start_time + make_interval(mins => c.ad_minutes + f.duration_minutes),
+                    c.timezone
+                )
+                FROM film f, cinema c
+                WHERE f.film_id = session.film_id AND c.cinema_id = session.cinema_id
+            ) as "end_time!",
+            format,
+            audio_language,
+            subtitle_language,
+            audio_description,
+            pricing_mode,
+            ticket_price,
+            session_surcharge(cinema_id, format) as "surcharge!",
+            ticket_price + session_surcharge(cinema_id, format) as "price!",
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = session.cinema_id
+            ) as "currency!",
+            status
         "#,
-        new_session.film_id,
-        new_session.cinema_id,
+        updated_session.hall_id,
         start_time,
-        new_session.ticket_price
+        pricing_mode,
+        ticket_price,
+        attributes.format,
+        attributes.audio_language,
+        attributes.subtitle_language,
+        attributes.audio_description,
+        session_id
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(HttpResponse::Created().json(session))
+    let affected_bookings = if changed.is_empty() {
+        Vec::new()
+    } else {
+        sqlx::query_as!(
+            AffectedBookingResponse,
+            r#"
+            SELECT
+                b.booking_id,
+                b.customer_id,
+                (cust.first_name || ' ' || cust.last_name) as "customer_name!",
+                cust.phone,
+                b.ticket_count
+            FROM booking b
+            JOIN customer cust ON b.customer_id = cust.customer_id
+            WHERE b.session_id = $1 AND b.status IN ('active', 'completed')
+            ORDER BY b.booking_id
+            "#,
+            session_id
+        )
+        .fetch_all(&mut *tx)
+        .await?
+    };
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Ok().json(SessionUpdateResponse {
+        session,
+        changed,
+        affected_bookings,
+    }))
+}
+
+// Отмена сеанса в одной транзакции: сеанс помечается отменённым,
+// активные и подтверждённые брони отменяются, по всем продажам
+// оформляются возвраты, удержания и места броней освобождаются.
+// Места продаж остаются за ними, чтобы по возвращённым билетам
+// было видно, какие места были проданы
+pub async fn cancel_session(
+    pool: web::Data<PgPool>,
+    session_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let session_id = session_id.into_inner();
+
+    let mut tx = pool.begin().await?;
+
+    let session = sqlx::query!(
+        r#"
+        SELECT s.status, c.currency::text as "currency!"
+        FROM session s
+        JOIN cinema c ON s.cinema_id = c.cinema_id
+        WHERE s.session_id = $1
+        FOR UPDATE OF s
+        "#,
+        session_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    if session.status == STATUS_CANCELLED {
+        return Err(AppError::Conflict("Session is already cancelled".into()));
+    }
+
+    sqlx::query!(
+        "UPDATE session SET status = $1, cancelled_at = NOW() WHERE session_id = $2",
+        STATUS_CANCELLED,
+        session_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let cancelled_bookings = sqlx::query_scalar!(
+        r#"
+        UPDATE booking
+        SET status = 'cancelled'
+        WHERE session_id = $1 AND status IN ('active', 'completed')
+        RETURNING booking_id
+        "#,
+        session_id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let refunds = sqlx::query_as!(
+        RefundResponse,
+        r#"
+        WITH inserted AS (
+            INSERT INTO refund (sale_id, amount, reason)
+            SELECT ts.sale_id, ts.total_price, $2
+            FROM ticket_sale ts
+            WHERE ts.session_id = $1
+            ON CONFLICT (sale_id) DO NOTHING
+            RETURNING refund_id, sale_id, amount, created_at
+        )
+        SELECT
+            i.refund_id,
+            i.sale_id,
+            ts.customer_id,
+            i.amount,
+            to_local_iso(i.created_at, session_timezone($1)) as "created_at!"
+        FROM inserted i
+        JOIN ticket_sale ts ON i.sale_id = ts.sale_id
+        ORDER BY i.sale_id
+        "#,
+        session_id,
+        REFUND_REASON_SESSION_CANCELLED
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    sqlx::query!("DELETE FROM seat_hold WHERE session_id = $1", session_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!(
+        r#"
+        DELETE FROM session_seat
+        WHERE session_id = $1
+        AND (booking_id IS NOT NULL OR hold_id IS NOT NULL)
+        "#,
+        session_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let total_refunded = refunds.iter().map(|r| r.amount).sum();
+
+    Ok(HttpResponse::Ok().json(SessionCancellationResponse {
+        session_id,
+        cancelled_bookings,
+        refunds,
+        total_refunded,
+        currency: session.currency,
+    }))
 }
 
+// Удалить можно только сеанс без продаж и броней,
+// иначе его нужно отменить, чтобы не потерять историю
 pub async fn delete_session(
     pool: web::Data<PgPool>,
     session_id: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
-    let result = sqlx::query!(
-        "DELETE FROM session WHERE session_id = $1",
-        session_id.into_inner()
+    let session_id = session_id.into_inner();
+
+    let has_orders = sqlx::query_scalar!(
+        r#"
+        SELECT
+            EXISTS(SELECT 1 FROM ticket_sale WHERE session_id = $1)
+            OR EXISTS(SELECT 1 FROM booking WHERE session_id = $1) as "exists!"
+        "#,
+        session_id
     )
-    .execute(pool.get_ref())
+    .fetch_one(pool.get_ref())
     .await?;
 
+    if has_orders {
+        return Err(AppError::Conflict(
+            "Session has sales or bookings; cancel it instead".into(),
+        ));
+    }
+
+    let result = sqlx::query!("DELETE FROM session WHERE session_id = $1", session_id)
+        .execute(pool.get_ref())
+        .await?;
+
     if result.rows_affected() == 0 {
         return Err(AppError::NotFound("Session not found".into()));
     }
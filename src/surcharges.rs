@@ -0,0 +1,122 @@
+use actix_web::{HttpResponse, web};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+use crate::money::{ensure_cinema_currency, validate_amount};
+use crate::sessions::SESSION_FORMATS;
+
+// Доплата за формат показа, прибавляется к ticket_price сеанса.
+// Форматы без доплаты в списке не возвращаются
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FormatSurchargeResponse {
+    pub format: String,
+    pub amount: Decimal,
+    pub currency: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FormatSurchargeRequest {
+    pub format: String, // "2d", "3d", "imax", "4dx"
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FormatSurchargesRequest {
+    pub currency: String, // валюта кинотеатра
+    pub surcharges: Vec<FormatSurchargeRequest>,
+}
+
+async fn fetch_surcharges(
+    pool: &PgPool,
+    cinema_id: i32,
+) -> Result<Vec<FormatSurchargeResponse>, AppError> {
+    let surcharges = sqlx::query_as!(
+        FormatSurchargeResponse,
+        r#"
+        SELECT fs.format, fs.amount, c.currency::text as "currency!"
+        FROM format_surcharge fs
+        JOIN cinema c ON fs.cinema_id = c.cinema_id
+        WHERE fs.cinema_id = $1
+        ORDER BY fs.format
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(surcharges)
+}
+
+pub async fn get_format_surcharges(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let surcharges = fetch_surcharges(pool.get_ref(), cinema_id).await?;
+
+    Ok(HttpResponse::Ok().json(surcharges))
+}
+
+// Полностью заменяет доплаты кинотеатра. Новые цены действуют и для уже
+// запланированных сеансов; проданные билеты сохраняют свою цену
+pub async fn update_format_surcharges(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    request: web::Json<FormatSurchargesRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_currency(pool.get_ref(), cinema_id, &request.currency).await?;
+
+    let mut formats = Vec::new();
+    let mut amounts = Vec::new();
+
+    for surcharge in &request.surcharges {
+        if !SESSION_FORMATS.contains(&surcharge.format.as_str()) {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown format {}, expected one of {:?}",
+                surcharge.format, SESSION_FORMATS
+            )));
+        }
+        if formats.contains(&surcharge.format) {
+            return Err(AppError::InvalidInput(format!(
+                "Format {} is listed twice",
+                surcharge.format
+            )));
+        }
+        validate_amount("Surcharge", surcharge.amount)?;
+        formats.push(surcharge.format.clone());
+        amounts.push(surcharge.amount);
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "DELETE FROM format_surcharge WHERE cinema_id = $1",
+        cinema_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO format_surcharge (cinema_id, format, amount)
+        SELECT $1, * FROM UNNEST($2::text[], $3::numeric[])
+        "#,
+        cinema_id,
+        &formats,
+        &amounts
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    let surcharges = fetch_surcharges(pool.get_ref(), cinema_id).await?;
+
+    Ok(HttpResponse::Ok().json(surcharges))
+}
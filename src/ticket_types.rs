@@ -0,0 +1,511 @@
+use actix_web::{HttpResponse, web};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+
+use crate::cinemas::ensure_cinema_exists;
+use crate::errors::AppError;
+use crate::money::{ensure_cinema_currency, round_money, validate_amount};
+
+// Тип, которым считаются все билеты, если состав заказа не указан
+pub const DEFAULT_TICKET_TYPE: &str = "adult";
+// С этого возрастного ограничения фильма детские билеты не продаются
+const ADULT_ONLY_AGE: u32 = 18;
+pub const MAX_PERCENT: Decimal = Decimal::from_parts(99999, 0, 0, false, 2); // 999.99
+
+// Стандартный набор для нового кинотеатра: код, название, процент, детский ли
+const DEFAULT_TICKET_TYPES: [(&str, &str, i64, bool); 4] = [
+    ("adult", "Взрослый", 100, false),
+    ("child", "Детский", 70, true),
+    ("student", "Студенческий", 80, false),
+    ("senior", "Пенсионный", 70, false),
+];
+
+// Цена билета: base * percent / 100 + adjustment, где base — цена сеанса
+// вместе с доплатой за формат. Отрицательной цена не бывает
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TicketTypeResponse {
+    pub ticket_type_id: i32,
+    pub cinema_id: i32,
+    pub code: String,
+    pub name: String,
+    pub percent: Decimal,
+    pub adjustment: Decimal, // со знаком минус — скидка
+    pub currency: String,
+    pub is_child: bool,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTicketTypeRequest {
+    pub code: String, // латиница в нижнем регистре, например "student"
+    pub name: String,
+    pub percent: Decimal,
+    pub adjustment: Option<Decimal>,
+    pub currency: String, // валюта adjustment, должна совпадать с валютой кинотеатра
+    pub is_child: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+// Строка заказа: сколько билетов какого типа
+#[derive(Debug, Clone, Deserialize)]
+pub struct TicketLineRequest {
+    pub ticket_type_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug)]
+struct TicketTypeRow {
+    ticket_type_id: i32,
+    code: String,
+    percent: Decimal,
+    adjustment: Decimal,
+    is_child: bool,
+    is_active: bool,
+}
+
+// Проверенная строка заказа
+#[derive(Debug)]
+pub struct TicketLine {
+    pub ticket_type_id: i32,
+    pub quantity: i32,
+    percent: Decimal,
+    adjustment: Decimal,
+}
+
+impl TicketLine {
+    pub fn unit_price(&self, base_price: Decimal) -> Decimal {
+        ticket_type_price(base_price, self.percent, self.adjustment)
+    }
+}
+
+pub fn ticket_type_price(base_price: Decimal, percent: Decimal, adjustment: Decimal) -> Decimal {
+    round_money(base_price * percent / Decimal::ONE_HUNDRED + adjustment).max(Decimal::ZERO)
+}
+
+// Возраст из ограничения фильма вида "16+"
+fn minimum_age(age_restriction: &str) -> Option<u32> {
+    let digits: String = age_restriction
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+
+    digits.parse().ok()
+}
+
+// Сколько билетов в составе заказа, если он указан
+pub fn requested_count(lines: Option<&[TicketLineRequest]>) -> Option<i32> {
+    lines.map(|lines| lines.iter().map(|line| line.quantity).sum())
+}
+
+// Проверяет состав заказа для сеанса: типы этого кинотеатра и активные,
+// каждый не больше одного раза, всего ticket_count билетов, без детских
+// на фильмы 18+. Без состава все билеты — DEFAULT_TICKET_TYPE
+pub async fn resolve_ticket_lines(
+    conn: &mut PgConnection,
+    session_id: i32,
+    lines: Option<&[TicketLineRequest]>,
+    ticket_count: i32,
+) -> Result<Vec<TicketLine>, AppError> {
+    let session = sqlx::query!(
+        r#"
+        SELECT s.cinema_id, f.title, f.age_restriction
+        FROM session s
+        JOIN film f ON s.film_id = f.film_id
+        WHERE s.session_id = $1
+        "#,
+        session_id
+    )
+    .fetch_optional(&mut *conn)
+    .await?
+    .ok_or_else(|| AppError::NotFound("Session not found".into()))?;
+
+    let types = sqlx::query_as!(
+        TicketTypeRow,
+        r#"
+        SELECT ticket_type_id, code, percent, adjustment, is_child, is_active
+        FROM ticket_type
+        WHERE cinema_id = $1
+        "#,
+        session.cinema_id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let requested = match lines {
+        Some(lines) => lines.to_vec(),
+        None => {
+            let default = types
+                .iter()
+                .find(|t| t.code == DEFAULT_TICKET_TYPE && t.is_active)
+                .ok_or_else(|| {
+                    AppError::InvalidInput(format!(
+                        "Cinema has no active {} ticket type, list ticket types explicitly",
+                        DEFAULT_TICKET_TYPE
+                    ))
+                })?;
+            vec![TicketLineRequest {
+                ticket_type_id: default.ticket_type_id,
+                quantity: ticket_count,
+            }]
+        }
+    };
+
+    let adults_only =
+        minimum_age(&session.age_restriction).is_some_and(|age| age >= ADULT_ONLY_AGE);
+    let mut resolved: Vec<TicketLine> = Vec::with_capacity(requested.len());
+
+    for line in &requested {
+        let ticket_type = types
+            .iter()
+            .find(|t| t.ticket_type_id == line.ticket_type_id && t.is_active)
+            .ok_or_else(|| {
+                AppError::InvalidInput(format!(
+                    "Ticket type {} is not available in this cinema",
+                    line.ticket_type_id
+                ))
+            })?;
+
+        if line.quantity <= 0 {
+            return Err(AppError::InvalidInput(
+                "Ticket quantity must be positive".into(),
+            ));
+        }
+        if resolved
+            .iter()
+            .any(|r| r.ticket_type_id == line.ticket_type_id)
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Ticket type {} is listed twice",
+                ticket_type.code
+            )));
+        }
+        if ticket_type.is_child && adults_only {
+            return Err(AppError::InvalidInput(format!(
+                "{} is rated {}, child tickets are not sold",
+                session.title, session.age_restriction
+            )));
+        }
+
+        resolved.push(TicketLine {
+            ticket_type_id: ticket_type.ticket_type_id,
+            quantity: line.quantity,
+            percent: ticket_type.percent,
+            adjustment: ticket_type.adjustment,
+        });
+    }
+
+    let total: i32 = resolved.iter().map(|r| r.quantity).sum();
+    if total != ticket_count {
+        return Err(AppError::InvalidInput(format!(
+            "Ticket types add up to {} tickets, but {} are requested",
+            total, ticket_count
+        )));
+    }
+
+    Ok(resolved)
+}
+
+// Заводит стандартные типы билетов новому кинотеатру
+pub async fn create_default_ticket_types(
+    conn: &mut PgConnection,
+    cinema_id: i32,
+) -> Result<(), AppError> {
+    let codes: Vec<String> = DEFAULT_TICKET_TYPES
+        .iter()
+        .map(|t| t.0.to_string())
+        .collect();
+    let names: Vec<String> = DEFAULT_TICKET_TYPES
+        .iter()
+        .map(|t| t.1.to_string())
+        .collect();
+    let percents: Vec<Decimal> = DEFAULT_TICKET_TYPES
+        .iter()
+        .map(|t| Decimal::from(t.2))
+        .collect();
+    let child: Vec<bool> = DEFAULT_TICKET_TYPES.iter().map(|t| t.3).collect();
+
+    sqlx::query!(
+        r#"
+        INSERT INTO ticket_type (cinema_id, code, name, percent, is_child)
+        SELECT $1, * FROM UNNEST($2::text[], $3::text[], $4::numeric[], $5::bool[])
+        "#,
+        cinema_id,
+        &codes,
+        &names,
+        &percents,
+        &child
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+fn validate_ticket_type(ticket_type: &CreateTicketTypeRequest) -> Result<(), AppError> {
+    let code_ok = ticket_type.code.len() <= 20
+        && ticket_type
+            .code
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_lowercase())
+        && ticket_type
+            .code
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if !code_ok {
+        return Err(AppError::InvalidInput(
+            "Ticket type code must be lowercase latin letters, digits and _".into(),
+        ));
+    }
+    if ticket_type.name.trim().is_empty() {
+        return Err(AppError::InvalidInput(
+            "Ticket type name is required".into(),
+        ));
+    }
+    validate_amount("Percent", ticket_type.percent)?;
+    if ticket_type.percent > MAX_PERCENT {
+        return Err(AppError::InvalidInput(format!(
+            "Percent cannot exceed {}",
+            MAX_PERCENT
+        )));
+    }
+    if let Some(adjustment) = ticket_type.adjustment {
+        validate_amount("Adjustment", adjustment.abs())?;
+    }
+
+    Ok(())
+}
+
+fn code_taken(code: &str) -> String {
+    format!("Ticket type {} already exists in this cinema", code)
+}
+
+// Понятная ошибка до записи. Параллельный запрос с тем же кодом всё равно
+// может успеть раньше, его ловит уникальный индекс (cinema_id, code)
+async fn ensure_code_is_free(
+    pool: &PgPool,
+    cinema_id: i32,
+    code: &str,
+    exclude_ticket_type_id: Option<i32>,
+) -> Result<(), AppError> {
+    let taken = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM ticket_type
+            WHERE cinema_id = $1
+            AND code = $2
+            AND ($3::int IS NULL OR ticket_type_id <> $3)
+        ) as "exists!"
+        "#,
+        cinema_id,
+        code,
+        exclude_ticket_type_id
+    )
+    .fetch_one(pool)
+    .await?;
+
+    if taken {
+        return Err(AppError::Conflict(code_taken(code)));
+    }
+
+    Ok(())
+}
+
+pub async fn get_ticket_types(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    ensure_cinema_exists(pool.get_ref(), cinema_id).await?;
+
+    let ticket_types = sqlx::query_as!(
+        TicketTypeResponse,
+        r#"
+        SELECT
+            tt.ticket_type_id,
+            tt.cinema_id,
+            tt.code,
+            tt.name,
+            tt.percent,
+            tt.adjustment,
+            c.currency::text as "currency!",
+            tt.is_child,
+            tt.is_active
+        FROM ticket_type tt
+        JOIN cinema c ON tt.cinema_id = c.cinema_id
+        WHERE tt.cinema_id = $1
+        ORDER BY tt.ticket_type_id
+        "#,
+        cinema_id
+    )
+    .fetch_all(pool.get_ref())
+    .await?;
+
+    Ok(HttpResponse::Ok().json(ticket_types))
+}
+
+pub async fn create_ticket_type(
+    pool: web::Data<PgPool>,
+    cinema_id: web::Path<i32>,
+    new_type: web::Json<CreateTicketTypeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let cinema_id = cinema_id.into_inner();
+    validate_ticket_type(&new_type)?;
+    ensure_cinema_currency(pool.get_ref(), cinema_id, &new_type.currency).await?;
+    ensure_code_is_free(pool.get_ref(), cinema_id, &new_type.code, None).await?;
+
+    let ticket_type = sqlx::query_as!(
+        TicketTypeResponse,
+        r#"
+        INSERT INTO ticket_type (cinema_id, code, name, percent, adjustment, is_child, is_active)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING
+            ticket_type_id,
+            cinema_id,
+            code,
+            name,
+            percent,
+            adjustment,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = ticket_type.cinema_id
+            ) as "currency!",
+            is_child,
+            is_active
+        "#,
+        cinema_id,
+        new_type.code,
+        new_type.name,
+        new_type.percent,
+        new_type.adjustment.unwrap_or(Decimal::ZERO),
+        new_type.is_child.unwrap_or(false),
+        new_type.is_active.unwrap_or(true)
+    )
+    .fetch_one(pool.get_ref())
+    .await
+    .map_err(AppError::on_unique_violation(code_taken(&new_type.code)))?;
+
+    Ok(HttpResponse::Created().json(ticket_type))
+}
+
+// Новые правила цены действуют для следующих продаж,
+// в уже проданных билетах цена сохранена
+pub async fn update_ticket_type(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    updated_type: web::Json<CreateTicketTypeRequest>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, ticket_type_id) = path.into_inner();
+    validate_ticket_type(&updated_type)?;
+    ensure_cinema_currency(pool.get_ref(), cinema_id, &updated_type.currency).await?;
+    ensure_code_is_free(
+        pool.get_ref(),
+        cinema_id,
+        &updated_type.code,
+        Some(ticket_type_id),
+    )
+    .await?;
+
+    let ticket_type = sqlx::query_as!(
+        TicketTypeResponse,
+        r#"
+        UPDATE ticket_type
+        SET
+            code = $1,
+            name = $2,
+            percent = $3,
+            adjustment = $4,
+            is_child = $5,
+            is_active = $6
+        WHERE cinema_id = $7 AND ticket_type_id = $8
+        RETURNING
+            ticket_type_id,
+            cinema_id,
+            code,
+            name,
+            percent,
+            adjustment,
+            (
+                SELECT c.currency::text FROM cinema c WHERE c.cinema_id = ticket_type.cinema_id
+            ) as "currency!",
+            is_child,
+            is_active
+        "#,
+        updated_type.code,
+        updated_type.name,
+        updated_type.percent,
+        updated_type.adjustment.unwrap_or(Decimal::ZERO),
+        updated_type.is_child.unwrap_or(false),
+        updated_type.is_active.unwrap_or(true),
+        cinema_id,
+        ticket_type_id
+    )
+    .fetch_optional(pool.get_ref())
+    .await
+    .map_err(AppError::on_unique_violation(code_taken(
+        &updated_type.code,
+    )))?;
+
+    match ticket_type {
+        Some(t) => Ok(HttpResponse::Ok().json(t)),
+        None => Err(AppError::NotFound("Ticket type not found".into())),
+    }
+}
+
+// Тип, по которому уже продавали или бронировали, не удаляется,
+// иначе пропадёт история цен. Его можно только деактивировать
+pub async fn delete_ticket_type(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+) -> Result<HttpResponse, AppError> {
+    let (cinema_id, ticket_type_id) = path.into_inner();
+
+    let exists = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1 FROM ticket_type WHERE cinema_id = $1 AND ticket_type_id = $2
+        ) as "exists!"
+        "#,
+        cinema_id,
+        ticket_type_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    if !exists {
+        return Err(AppError::NotFound("Ticket type not found".into()));
+    }
+
+    let in_use = sqlx::query_scalar!(
+        r#"
+        SELECT (
+            EXISTS(SELECT 1 FROM ticket_sale_item WHERE ticket_type_id = $1)
+            OR EXISTS(SELECT 1 FROM booking_item WHERE ticket_type_id = $1)
+        ) as "exists!"
+        "#,
+        ticket_type_id
+    )
+    .fetch_one(pool.get_ref())
+    .await?;
+
+    let in_use_message = "Ticket type has sales or bookings, deactivate it instead";
+    if in_use {
+        return Err(AppError::Conflict(in_use_message.into()));
+    }
+
+    let result = sqlx::query!(
+        "DELETE FROM ticket_type WHERE cinema_id = $1 AND ticket_type_id = $2",
+        cinema_id,
+        ticket_type_id
+    )
+    .execute(pool.get_ref())
+    .await
+    .map_err(AppError::on_foreign_key_violation(in_use_message.into()))?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::NotFound("Ticket type not found".into()));
+    }
+
+    Ok(HttpResponse::NoContent().finish())
+}
@@ -1,8 +1,17 @@
 use actix_web::{HttpResponse, web};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
-use sqlx::PgPool;
+use sqlx::{PgConnection, PgExecutor, PgPool};
 
+use crate::availability::ensure_capacity;
 use crate::errors::AppError;
+use crate::holds::consume_hold;
+use crate::money::{ensure_currency, validate_amount};
+use crate::pricing::sale_pricing;
+use crate::seats::{
+    SeatHolder, WithSeats, assign_seats, fetch_assigned_seats, resolve_ticket_count,
+};
+use crate::ticket_types::{TicketLineRequest, requested_count, resolve_ticket_lines};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TicketSaleResponse {
@@ -12,13 +21,38 @@ pub struct TicketSaleResponse {
     pub employee_id: i32,
     pub ticket_count: i32,
     pub sale_time: String,
-    pub total_price: f64,
+    pub unit_price: Decimal, // базовая цена места без категории с доплатой за формат на момент продажи
+    pub discount: Decimal,   // скидка на всю продажу
+    pub total_price: Decimal,
+    pub currency: String,
 }
 
+// Строка чека: билеты одного типа на места одной категории
+// по цене на момент продажи
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SaleItemResponse {
+    pub ticket_type_id: i32,
+    pub code: String,
+    pub name: String,
+    pub seat_category: Option<String>, // None — свободная рассадка
+    pub quantity: i32,
+    pub unit_price: Decimal,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SaleReceipt {
+    #[serde(flatten)]
+    pub sale: TicketSaleResponse,
+    pub items: Vec<SaleItemResponse>,
+}
+
+// Статистика считается отдельно по каждой валюте:
+// суммы в разных валютах складывать нельзя
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SalesStatsResponse {
+    pub currency: String,
     pub total_sales: i64,
-    pub total_revenue: f64,
+    pub total_revenue: Decimal,
     pub avg_tickets_per_sale: f64,
 }
 
@@ -27,26 +61,170 @@ pub struct CreateTicketSaleRequest {
     pub session_id: i32,
     pub customer_id: i32,
     pub employee_id: i32,
-    pub ticket_count: i32,
+    pub ticket_count: Option<i32>, // для сеансов со свободной рассадкой
+    pub seat_ids: Option<Vec<i32>>, // для залов со схемой мест
+    pub hold_id: Option<i32>,      // удержание, по которому оформляется заказ
+    pub discount: Option<Decimal>, // скидка кассира на всю продажу
+    pub currency: Option<String>,  // валюта скидки, обязательна вместе с discount
+    pub tickets: Option<Vec<TicketLineRequest>>, // состав по типам, по умолчанию все взрослые
+}
+
+struct SaleItem {
+    ticket_type_id: i32,
+    seat_category: Option<String>,
+    quantity: i32,
+    unit_price: Decimal,
+}
+
+// Категории мест продажи в порядке seat_ids
+async fn fetch_seat_categories(
+    conn: &mut PgConnection,
+    seat_ids: &[i32],
+) -> Result<Vec<Option<String>>, AppError> {
+    if seat_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let seats = sqlx::query!(
+        "SELECT seat_id, category FROM seat WHERE seat_id = ANY($1)",
+        seat_ids
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(seat_ids
+        .iter()
+        .map(|seat_id| {
+            seats
+                .iter()
+                .find(|seat| seat.seat_id == *seat_id)
+                .map(|seat| seat.category.clone())
+        })
+        .collect())
+}
+
+async fn fetch_sale_items<'e>(
+    executor: impl PgExecutor<'e>,
+    sale_id: i32,
+) -> Result<Vec<SaleItemResponse>, AppError> {
+    let items = sqlx::query_as!(
+        SaleItemResponse,
+        r#"
+        SELECT i.ticket_type_id, tt.code, tt.name, i.seat_category, i.quantity, i.unit_price
+        FROM ticket_sale_item i
+        JOIN ticket_type tt ON i.ticket_type_id = tt.ticket_type_id
+        WHERE i.sale_id = $1
+        ORDER BY i.item_id
+        "#,
+        sale_id
+    )
+    .fetch_all(executor)
+    .await?;
+
+    Ok(items)
 }
 
 pub async fn create_ticket_sale(
     pool: web::Data<PgPool>,
     new_sale: web::Json<CreateTicketSaleRequest>,
 ) -> Result<HttpResponse, AppError> {
+    let mut seat_ids = new_sale.seat_ids.clone().unwrap_or_default();
+
+    let mut tx = pool.begin().await?;
+
+    let lines = new_sale.tickets.as_deref();
+    let mut requested = new_sale.ticket_count.or(requested_count(lines));
+
+    if let Some(hold_id) = new_sale.hold_id {
+        let held =
+            consume_hold(&mut tx, hold_id, new_sale.session_id, new_sale.customer_id).await?;
+        held.fill_request(&mut seat_ids, &mut requested)?;
+    }
+
+    let ticket_count =
+        resolve_ticket_count(&mut tx, new_sale.session_id, requested, &seat_ids).await?;
+
+    ensure_capacity(&mut tx, new_sale.session_id, ticket_count).await?;
+
+    // Сеанс заблокирован в ensure_capacity: ни цена, ни заполненность,
+    // от которой зависит динамическая цена, не изменятся до конца транзакции
+    let seat_categories = fetch_seat_categories(&mut tx, &seat_ids).await?;
+    let price = sale_pricing(&mut tx, new_sale.session_id, &seat_categories).await?;
+    let ticket_lines =
+        resolve_ticket_lines(&mut tx, new_sale.session_id, lines, ticket_count).await?;
+
+    // Типы билетов раскладываются по местам в порядке seat_ids
+    let mut sale_items: Vec<SaleItem> = Vec::new();
+    let mut categories = seat_categories.iter();
+    for line in &ticket_lines {
+        for _ in 0..line.quantity {
+            let category = categories.next().cloned().flatten();
+            match sale_items.iter_mut().find(|item| {
+                item.ticket_type_id == line.ticket_type_id && item.seat_category == category
+            }) {
+                Some(item) => item.quantity += 1,
+                None => sale_items.push(SaleItem {
+                    ticket_type_id: line.ticket_type_id,
+                    unit_price: line.unit_price(price.base_price(category.as_deref())),
+                    seat_category: category,
+                    quantity: 1,
+                }),
+            }
+        }
+    }
+
+    let type_ids: Vec<i32> = sale_items.iter().map(|i| i.ticket_type_id).collect();
+    let item_categories: Vec<Option<String>> =
+        sale_items.iter().map(|i| i.seat_category.clone()).collect();
+    let quantities: Vec<i32> = sale_items.iter().map(|i| i.quantity).collect();
+    let unit_prices: Vec<Decimal> = sale_items.iter().map(|i| i.unit_price).collect();
+    let subtotal: Decimal = quantities
+        .iter()
+        .zip(&unit_prices)
+        .map(|(quantity, unit_price)| Decimal::from(*quantity) * unit_price)
+        .sum();
+    let discount = new_sale.discount.unwrap_or(Decimal::ZERO);
+    validate_amount("Discount", discount)?;
+    if new_sale.discount.is_some() {
+        let currency = new_sale
+            .currency
+            .as_deref()
+            .ok_or_else(|| AppError::InvalidInput("Discount requires a currency".into()))?;
+        ensure_currency(&price.currency, currency)?;
+    }
+    if discount > subtotal {
+        return Err(AppError::InvalidInput(format!(
+            "Discount {} exceeds the sale amount {} {}",
+            discount, subtotal, price.currency
+        )));
+    }
+
     let sale = sqlx::query_as!(
         TicketSaleResponse,
         r#"
         WITH inserted AS (
-            INSERT INTO ticket_sale (session_id, customer_id, employee_id, ticket_count)
-            VALUES ($1, $2, $3, $4)
+            INSERT INTO ticket_sale (
+                session_id,
+                customer_id,
+                employee_id,
+                ticket_count,
+                unit_price,
+                discount,
+                total_price,
+                currency
+            )
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
             RETURNING
                 sale_id,
                 session_id,
                 customer_id,
                 employee_id,
                 ticket_count,
-                sale_time::text
+                sale_time,
+                unit_price,
+                discount,
+                total_price,
+                currency
         )
         SELECT
             i.sale_id,
@@ -54,20 +232,57 @@ pub async fn create_ticket_sale(
             i.customer_id,
             i.employee_id,
             i.ticket_count,
-            i.sale_time,
-            (i.ticket_count * s.ticket_price) as total_price
+            to_local_iso(i.sale_time, c.timezone) as "sale_time!",
+            i.unit_price,
+            i.discount,
+            i.total_price,
+            i.currency::text as "currency!"
         FROM inserted i
         JOIN session s ON i.session_id = s.session_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
         "#,
         new_sale.session_id,
         new_sale.customer_id,
         new_sale.employee_id,
-        new_sale.ticket_count
+        ticket_count,
+        price.list_price,
+        discount,
+        subtotal - discount,
+        price.currency
     )
-    .fetch_one(pool.get_ref())
+    .fetch_one(&mut *tx)
     .await?;
 
-    Ok(HttpResponse::Created().json(sale))
+    sqlx::query!(
+        r#"
+        INSERT INTO ticket_sale_item (sale_id, ticket_type_id, seat_category, quantity, unit_price)
+        SELECT $1, * FROM UNNEST($2::int[], $3::text[], $4::int[], $5::numeric[])
+        "#,
+        sale.sale_id,
+        &type_ids,
+        &item_categories,
+        &quantities,
+        &unit_prices
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let items = fetch_sale_items(&mut *tx, sale.sale_id).await?;
+
+    let seats = assign_seats(
+        &mut tx,
+        new_sale.session_id,
+        &seat_ids,
+        SeatHolder::Sale(sale.sale_id),
+    )
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(HttpResponse::Created().json(WithSeats {
+        inner: SaleReceipt { sale, items },
+        seats,
+    }))
 }
 
 pub async fn get_sales_stats(pool: web::Data<PgPool>) -> Result<HttpResponse, AppError> {
@@ -75,14 +290,17 @@ pub async fn get_sales_stats(pool: web::Data<PgPool>) -> Result<HttpResponse, Ap
         SalesStatsResponse,
         r#"
         SELECT
+            ts.currency::text as "currency!",
             COUNT(*) as total_sales,
-            COALESCE(SUM(ticket_count * s.ticket_price), 0) as total_revenue,
-            COALESCE(AVG(ticket_count), 0) as avg_tickets_per_sale
+            COALESCE(SUM(ts.total_price) FILTER (WHERE r.refund_id IS NULL), 0) as total_revenue,
+            COALESCE(AVG(ts.ticket_count), 0)::float8 as avg_tickets_per_sale
         FROM ticket_sale ts
-        JOIN session s ON ts.session_id = s.session_id
+        LEFT JOIN refund r ON ts.sale_id = r.sale_id
+        GROUP BY ts.currency
+        ORDER BY ts.currency
         "#
     )
-    .fetch_one(pool.get_ref())
+    .fetch_all(pool.get_ref())
     .await?;
 
     Ok(HttpResponse::Ok().json(stats))
@@ -92,6 +310,8 @@ pub async fn get_ticket_sale(
     pool: web::Data<PgPool>,
     sale_id: web::Path<i32>,
 ) -> Result<HttpResponse, AppError> {
+    let sale_id = sale_id.into_inner();
+
     let sale = sqlx::query_as!(
         TicketSaleResponse,
         r#"
@@ -101,19 +321,27 @@ pub async fn get_ticket_sale(
             ts.customer_id,
             ts.employee_id,
             ts.ticket_count,
-            ts.sale_time::text,
-            (ts.ticket_count * s.ticket_price) as total_price
+            to_local_iso(ts.sale_time, c.timezone) as "sale_time!",
+            ts.unit_price,
+            ts.discount,
+            ts.total_price,
+            ts.currency::text as "currency!"
         FROM ticket_sale ts
         JOIN session s ON ts.session_id = s.session_id
+        JOIN cinema c ON s.cinema_id = c.cinema_id
         WHERE ts.sale_id = $1
         "#,
-        sale_id.into_inner()
+        sale_id
     )
     .fetch_optional(pool.get_ref())
-    .await?;
+    .await?
+    .ok_or_else(|| AppError::NotFound("Ticket sale not found".into()))?;
 
-    match sale {
-        Some(s) => Ok(HttpResponse::Ok().json(s)),
-        None => Err(AppError::NotFound("Ticket sale not found".into())),
-    }
+    let items = fetch_sale_items(pool.get_ref(), sale_id).await?;
+    let seats = fetch_assigned_seats(pool.get_ref(), SeatHolder::Sale(sale_id)).await?;
+
+    Ok(HttpResponse::Ok().json(WithSeats {
+        inner: SaleReceipt { sale, items },
+        seats,
+    }))
 }